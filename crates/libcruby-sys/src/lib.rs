@@ -56,6 +56,9 @@ impl RubyException {
 
 pub const EMPTY_EXCEPTION: RubyException = RubyException(0);
 
+// The state `rb_protect` reports for a raised exception (`TAG_RAISE` in vm_core.h)
+pub const RAISED_EXCEPTION: RubyException = RubyException(6);
+
 #[repr(C)]
 pub enum st_retval {
     ST_CONTINUE,
@@ -64,6 +67,47 @@ pub enum st_retval {
     // ST_CHECK
 }
 
+#[repr(C)]
+pub struct rb_data_type_function_t {
+    pub dmark: Option<extern "C" fn(data: *mut void)>,
+    pub dfree: Option<extern "C" fn(data: *mut void)>,
+    pub dsize: Option<extern "C" fn(data: *const void) -> libc::size_t>,
+    // Added in Ruby 2.7, older versions ignore it
+    pub dcompact: Option<extern "C" fn(data: *mut void)>,
+    pub reserved: [*mut void; 1]
+}
+
+#[repr(C)]
+pub struct rb_data_type_t {
+    pub wrap_struct_name: c_string,
+    pub function: rb_data_type_function_t,
+    pub parent: *const rb_data_type_t,
+    pub data: *mut void,
+    pub flags: VALUE
+}
+
+// Data types are only ever defined as immutable statics
+unsafe impl Sync for rb_data_type_t {}
+
+pub const RUBY_TYPED_FREE_IMMEDIATELY: VALUE = VALUE(1 as *mut void);
+
+// RUBY_TYPED_FREE_IMMEDIATELY | RUBY_TYPED_FROZEN_SHAREABLE, as VALUEs can't be
+// or-ed in a static. Added in Ruby 3.0, older versions ignore it
+pub const RUBY_TYPED_FREE_IMMEDIATELY_FROZEN_SHAREABLE: VALUE = VALUE(0x101 as *mut void);
+
+// Flags for `rb_integer_pack` and `rb_integer_unpack` (see ruby/intern.h)
+pub const INTEGER_PACK_LSWORD_FIRST: libc::c_int = 0x02;
+pub const INTEGER_PACK_NATIVE: libc::c_int = 0x40;
+pub const INTEGER_PACK_2COMP: libc::c_int = 0x80;
+pub const INTEGER_PACK_NEGATIVE: libc::c_int = 0x200;
+
+// Flags for `rb_str_encode` (see ruby/encoding.h)
+pub const ECONV_INVALID_REPLACE: libc::c_int = 0x02;
+pub const ECONV_UNDEF_REPLACE: libc::c_int = 0x20;
+
+// An opaque `rb_encoding *`
+pub type rb_encoding = void;
+
 #[cfg_attr(windows, link(name="helix-runtime"))]
 extern "C" {
     #[link_name = "HELIX_RUNTIME_VERSION"]
@@ -93,12 +137,24 @@ extern "C" {
     #[link_name = "rb_cBasicObject"]
     pub static rb_cBasicObject: VALUE;
 
+    #[link_name = "rb_eException"]
+    pub static rb_eException: VALUE;
+
     #[link_name = "rb_eRuntimeError"]
     pub static rb_eRuntimeError: VALUE;
 
     #[link_name = "rb_eTypeError"]
     pub static rb_eTypeError: VALUE;
 
+    #[link_name = "rb_eArgError"]
+    pub static rb_eArgError: VALUE;
+
+    #[link_name = "rb_eLocalJumpError"]
+    pub static rb_eLocalJumpError: VALUE;
+
+    #[link_name = "rb_eRangeError"]
+    pub static rb_eRangeError: VALUE;
+
     #[link_name = "HELIX_RSTRING_LEN"]
     pub fn RSTRING_LEN(string: VALUE) -> isize;
 
@@ -207,6 +263,7 @@ extern "C" {
     // fn rb_define_variable(name: c_string, value: *const VALUE);
     pub fn rb_obj_class(obj: VALUE) -> VALUE;
     pub fn rb_obj_classname(obj: VALUE) -> c_string;
+    pub fn rb_obj_is_kind_of(obj: VALUE, class: VALUE) -> VALUE;
     pub fn rb_const_get(class: VALUE, name: ID) -> VALUE;
     pub fn rb_define_global_const(name: c_string, value: VALUE);
     pub fn rb_define_module(name: c_string) -> VALUE;
@@ -214,13 +271,33 @@ extern "C" {
     pub fn rb_define_class(name: c_string, superclass: VALUE) -> VALUE;
     pub fn rb_define_class_under(namespace: VALUE, name: c_string, superclass: VALUE) -> VALUE;
     pub fn rb_define_alloc_func(class: VALUE, func: extern "C" fn(class: VALUE) -> VALUE);
+    pub fn rb_get_alloc_func(class: VALUE) -> Option<extern "C" fn(class: VALUE) -> VALUE>;
     pub fn rb_define_method(class: VALUE, name: c_string, func: c_func, arity: isize);
+    pub fn rb_define_private_method(class: VALUE, name: c_string, func: c_func, arity: isize);
+    pub fn rb_define_protected_method(class: VALUE, name: c_string, func: c_func, arity: isize);
     pub fn rb_define_singleton_method(class: VALUE, name: c_string, func: c_func, arity: isize);
+    pub fn rb_singleton_class(object: VALUE) -> VALUE;
+    pub fn rb_define_module_function(module: VALUE, name: c_string, func: c_func, arity: isize);
     pub fn rb_undef_method(class: VALUE, name: c_string);
-    pub fn rb_enc_get_index(obj: VALUE) -> isize;
-    pub fn rb_utf8_encindex() -> isize;
+    pub fn rb_enc_get_index(obj: VALUE) -> libc::c_int;
+    pub fn rb_utf8_encindex() -> libc::c_int;
+    pub fn rb_enc_from_index(index: libc::c_int) -> *mut rb_encoding;
+    pub fn rb_utf8_encoding() -> *mut rb_encoding;
+    pub fn rb_enc_from_encoding(encoding: *mut rb_encoding) -> VALUE;
+    pub fn rb_enc_associate_index(obj: VALUE, index: libc::c_int) -> VALUE;
+    pub fn rb_str_conv_enc(string: VALUE, from: *mut rb_encoding, to: *mut rb_encoding) -> VALUE;
+    pub fn rb_str_encode(string: VALUE, to: VALUE, ecflags: libc::c_int, ecopts: VALUE) -> VALUE;
     pub fn rb_sprintf(specifier: c_string, ...) -> VALUE;
     pub fn rb_inspect(value: VALUE) -> VALUE;
+    pub fn rb_str_new(string: c_string, len: libc::c_long) -> VALUE;
+    pub fn rb_str_locktmp(string: VALUE) -> VALUE;
+    pub fn rb_str_unlocktmp(string: VALUE) -> VALUE;
+    pub fn rb_integer_pack(value: VALUE, words: *mut void, numwords: libc::size_t, wordsize: libc::size_t, nails: libc::size_t, flags: libc::c_int) -> libc::c_int;
+    pub fn rb_absint_size(value: VALUE, nlz_bits: *mut libc::c_int) -> libc::size_t;
+    pub fn rb_integer_unpack(words: *const void, numwords: libc::size_t, wordsize: libc::size_t, nails: libc::size_t, flags: libc::c_int) -> VALUE;
+    pub fn rb_ivar_set(object: VALUE, name: ID, value: VALUE) -> VALUE;
+    pub fn rb_attr_get(object: VALUE, name: ID) -> VALUE;
+    pub fn rb_define_attr(class: VALUE, name: c_string, read: libc::c_int, write: libc::c_int);
     pub fn rb_intern(string: c_string) -> ID;
     pub fn rb_intern_str(string: VALUE) -> ID;
     pub fn rb_sym2id(symbol: VALUE) -> ID;
@@ -235,16 +312,41 @@ extern "C" {
     pub fn rb_hash_aset(hash: VALUE, key: VALUE, value: VALUE) -> VALUE;
     pub fn rb_hash_foreach(hash: VALUE, f: extern "C" fn(key: VALUE, value: VALUE, farg: *mut void) -> st_retval, farg: *mut void);
     pub fn rb_gc_mark(value: VALUE);
+    pub fn rb_gc_register_mark_object(object: VALUE);
+
+    #[link_name = "HELIX_rb_gc_mark_movable"]
+    pub fn rb_gc_mark_movable(value: VALUE);
+
+    #[link_name = "HELIX_rb_gc_location"]
+    pub fn rb_gc_location(value: VALUE) -> VALUE;
+
     pub fn rb_funcall(value: VALUE, mid: ID, argc: libc::c_int, ...) -> VALUE;
     pub fn rb_funcallv(value: VALUE, mid: ID, argc: libc::c_int, argv: *const VALUE) -> VALUE;
     pub fn rb_scan_args(argc: libc::c_int, argv: *const VALUE, fmt: c_string, ...);
     pub fn rb_block_given_p() -> bool;
+
+    #[link_name = "HELIX_rb_keyword_given_p"]
+    pub fn rb_keyword_given_p() -> bool;
+
     pub fn rb_yield(value: VALUE) -> VALUE;
+    pub fn rb_yield_values2(argc: libc::c_int, argv: *const VALUE) -> VALUE;
+    pub fn rb_block_proc() -> VALUE;
     pub fn rb_obj_dup(value: VALUE) -> VALUE;
     pub fn rb_obj_init_copy(value: VALUE, orig: VALUE) -> VALUE;
 
     pub fn rb_raise(exc: VALUE, string: c_string, ...) -> !;
+    pub fn rb_exc_new_str(class: VALUE, message: VALUE) -> VALUE;
+    pub fn rb_exc_raise(exception: VALUE) -> !;
+    pub fn rb_error_frozen_object(frozen_obj: VALUE) -> !;
     pub fn rb_jump_tag(state: RubyException) -> !;
+    pub fn rb_errinfo() -> VALUE;
+    pub fn rb_set_errinfo(exception: VALUE);
+    pub fn rb_thread_check_ints();
+    pub fn rb_thread_call_without_gvl(func: extern "C" fn(data: *mut void) -> *mut void,
+                                      data1: *mut void,
+                                      ubf: extern "C" fn(data: *mut void),
+                                      data2: *mut void)
+                                      -> *mut void;
     pub fn rb_protect(try: extern "C" fn(v: *mut void) -> VALUE,
                       arg: *mut void,
                       state: *mut RubyException)
@@ -259,6 +361,9 @@ extern "C" {
     #[link_name = "HELIX_Data_Wrap_Struct"]
     pub fn Data_Wrap_Struct(klass: VALUE, mark: extern "C" fn(*mut void), free: extern "C" fn(*mut void), data: *mut void) -> VALUE;
 
+    pub fn rb_data_typed_object_wrap(klass: VALUE, data: *mut void, data_type: *const rb_data_type_t) -> VALUE;
+    pub fn rb_typeddata_is_kind_of(obj: VALUE, data_type: *const rb_data_type_t) -> libc::c_int;
+
     #[link_name = "HELIX_Data_Get_Struct_Value"]
     pub fn Data_Get_Struct_Value(obj: VALUE) -> *mut void;
 
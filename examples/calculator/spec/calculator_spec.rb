@@ -1,4 +1,5 @@
 require "spec_helper"
+require "timeout"
 
 describe Calculator do
   it "can add numbers" do
@@ -16,6 +17,134 @@ describe Calculator do
     expect(Calculator.divide(4, 2)).to eq(2)
     expect { Calculator.divide(4, 0) }.to raise_error("Division by zero")
   end
+
+  it "can take a splat argument" do
+    expect(Calculator.sum).to eq(0)
+    expect(Calculator.sum(1)).to eq(1)
+    expect(Calculator.sum(1, 2.5, 3)).to eq(6.5)
+    expect { Calculator.sum(1, "two") }.to raise_error(TypeError)
+  end
+
+  it "can take required arguments before a splat argument" do
+    expect(Calculator.scale(2)).to eq([])
+    expect(Calculator.scale(2, 1, 2, 3)).to eq([2, 4, 6])
+    expect { Calculator.scale }.to raise_error(ArgumentError, "wrong number of arguments (given 0, expected 1+)")
+  end
+
+  it "can take optional arguments with defaults" do
+    expect(Calculator.round(1.2345)).to eq(1)
+    expect(Calculator.round(1.2345, 2)).to eq(1.23)
+
+    expect(Calculator.clamp(-5)).to eq(0)
+    expect(Calculator.clamp(5)).to eq(1)
+    expect(Calculator.clamp(5, 0, 10)).to eq(5)
+    expect(Calculator.clamp(-5, -2)).to eq(-2)
+  end
+
+  it "raises ArgumentError for the wrong number of arguments" do
+    expect { Calculator.round }.to raise_error(ArgumentError, "wrong number of arguments (given 0, expected 1..2)")
+    expect { Calculator.clamp(1, 2, 3, 4) }.to raise_error(ArgumentError, "wrong number of arguments (given 4, expected 1..3)")
+    expect { Calculator.add(1) }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 2)")
+  end
+
+  it "can take keyword arguments" do
+    expect(Calculator.lerp(0, 10, at: 0.5)).to eq(5)
+    expect(Calculator.lerp(0, 10, at: 1.5)).to eq(15)
+    expect(Calculator.lerp(0, 10, at: 1.5, clamp: true)).to eq(10)
+    expect(Calculator.lerp(0, 10, clamp: true, at: -1)).to eq(0)
+    expect { Calculator.lerp(0, 10, at: "half") }.to raise_error(TypeError)
+  end
+
+  it "can take keyword arguments after a splat argument" do
+    expect(Calculator.total).to eq(0)
+    expect(Calculator.total(1, 2, 3)).to eq(6)
+    expect(Calculator.total(1, 2, 3, initial: 10)).to eq(16)
+    expect(Calculator.total(initial: 10)).to eq(10)
+  end
+
+  it "raises ArgumentError for missing or unknown keywords" do
+    expect { Calculator.lerp(0, 10) }.to raise_error(ArgumentError, "missing keyword: :at")
+    expect { Calculator.lerp(0, 10, at: 0.5, step: 1) }.to raise_error(ArgumentError, "unknown keyword: :step")
+    expect { Calculator.lerp(0, 10, at: 0.5, step: 1, by: 2) }.to raise_error(ArgumentError, "unknown keywords: :step, :by")
+    expect { Calculator.lerp(0, at: 0.5) }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 2)")
+    expect { Calculator.lerp(0) }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 2)")
+  end
+
+  # Before Ruby 2.7 a trailing Hash is always passed as keywords
+  if RUBY_VERSION >= "2.7"
+    it "does not take a positional Hash as keywords" do
+      expect { Calculator.total({ initial: 10 }) }.to raise_error(TypeError)
+      expect { Calculator.lerp(0, 10, { at: 0.5 }) }.to raise_error(ArgumentError, "wrong number of arguments (given 3, expected 2)")
+    end
+  end
+
+  it "can take and return integers of any width" do
+    expect(Calculator.factorial(0)).to eq(1)
+    expect(Calculator.factorial(20)).to eq(2432902008176640000)
+    expect(Calculator.factorial(34)).to eq((1..34).reduce(:*))
+    expect(Calculator.factorial(35)).to eq(nil)
+
+    expect(Calculator.negate(2**127 - 1)).to eq(-(2**127 - 1))
+    expect(Calculator.negate(-2**100)).to eq(2**100)
+    expect(Calculator.negate(-2**127)).to eq(nil)
+
+    expect(Calculator.widen(-128, -32768, 65535)).to eq(-128 - 32768 + 65535)
+  end
+
+  it "can take and return arbitrary-precision integers" do
+    values = [0, 1, -1, 2**30, 2**62 - 1, 2**62, -2**62 - 1, 2**63, -2**63, 2**64 - 1, 2**64, -2**64, 2**200 + 1, -3**150]
+
+    values.each do |value|
+      expect(Calculator.big_negate(value)).to eq(-value)
+      expect(Calculator.big_negate(-value)).to eq(value)
+    end
+
+    expect(Calculator.big_factorial(0)).to eq(1)
+    expect(Calculator.big_factorial(20)).to eq(2432902008176640000)
+    expect(Calculator.big_factorial(21)).to eq((1..21).reduce(:*))
+    expect(Calculator.big_factorial(100)).to eq((1..100).reduce(:*))
+
+    expect { Calculator.big_negate(1.5) }.to raise_error(TypeError)
+  end
+
+  it "raises RangeError for integers that don't fit" do
+    expect { Calculator.factorial(256) }.to raise_error(RangeError, "256 is out of range for an 8-bit unsigned integer")
+    expect { Calculator.factorial(-1) }.to raise_error(RangeError, "-1 is out of range for an 8-bit unsigned integer")
+    expect { Calculator.negate(2**127) }.to raise_error(RangeError, "#{2**127} is out of range for a 128-bit signed integer")
+    expect { Calculator.negate(2**200) }.to raise_error(RangeError, /out of range for a 128-bit signed integer/)
+    expect { Calculator.widen(128, 0, 0) }.to raise_error(RangeError, /8-bit signed integer/)
+    expect { Calculator.widen(0, -32769, 0) }.to raise_error(RangeError, /16-bit signed integer/)
+    expect { Calculator.widen(0, 0, -1) }.to raise_error(RangeError, /16-bit unsigned integer/)
+    expect { Calculator.round(1.5, 2**31) }.to raise_error(RangeError, /32-bit signed integer/)
+    expect { Calculator.sum_of_squares(2**64) }.to raise_error(RangeError, /64-bit unsigned integer/)
+    expect { Calculator.sum_of_squares(-1) }.to raise_error(RangeError, /64-bit unsigned integer/)
+    expect { Calculator.factorial(1.5) }.to raise_error(TypeError)
+  end
+
+  it "can run methods without the GVL" do
+    expect(Calculator.sum_of_squares(3)).to eq(14)
+
+    ticks = 0
+    ticker = Thread.new { loop { ticks += 1; sleep 0.001 } }
+
+    expect(Calculator.spin(0.2)).to eq(true)
+    ticker.kill
+
+    expect(ticks).to be > 10
+  end
+
+  it "interrupts methods running without the GVL" do
+    started = Time.now
+
+    expect { Timeout.timeout(0.05) { Calculator.spin(10) } }.to raise_error(Timeout::Error)
+    expect(Time.now - started).to be < 5
+  end
+
+  it "defines methods with optional arguments as variadic" do
+    expect(Calculator.method(:add).arity).to eq(2)
+    expect(Calculator.method(:round).arity).to eq(-1)
+    expect(Calculator.method(:sum).arity).to eq(-1)
+  end
 end
 
 describe Adder do
@@ -23,6 +152,11 @@ describe Adder do
     expect(Adder.new(1.23).(-4.56)).to eq(1.23 - 4.56)
     expect(Adder.new(3).(5)).to eq(8)
   end
+
+  it "can be created with a default value" do
+    expect(Adder.new.(5)).to eq(5)
+    expect { Adder.new(1, 2) }.to raise_error(ArgumentError, "wrong number of arguments (given 2, expected 0..1)")
+  end
 end
 
 describe Multiplier do
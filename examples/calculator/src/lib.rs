@@ -2,6 +2,11 @@
 
 #[macro_use]
 extern crate helix;
+extern crate num_bigint;
+
+use num_bigint::{BigInt, BigUint};
+use std::thread;
+use std::time::{Duration, Instant};
 
 ruby! {
     class Calculator {
@@ -16,6 +21,72 @@ ruby! {
         def divide(lhs: f64, rhs: f64) -> Result<f64, &'static str> {
             Divider::new(lhs).call(rhs)
         }
+
+        def sum(*values: Vec<f64>) -> f64 {
+            values.iter().sum()
+        }
+
+        def round(value: f64, digits: i32 = 0) -> f64 {
+            let factor = 10f64.powi(digits);
+            (value * factor).round() / factor
+        }
+
+        def clamp(value: f64, min: f64 = 0.0, max: f64 = 1.0) -> f64 {
+            value.max(min).min(max)
+        }
+
+        def scale(factor: f64, *values: Vec<f64>) -> Vec<f64> {
+            values.into_iter().map(|value| value * factor).collect()
+        }
+
+        def lerp(from: f64, to: f64, #[kw] at: f64, #[kw] clamp: bool = false) -> f64 {
+            let at = if clamp { at.max(0.0).min(1.0) } else { at };
+            from + (to - from) * at
+        }
+
+        def total(*values: Vec<f64>, #[kw] initial: f64 = 0.0) -> f64 {
+            values.iter().fold(initial, |sum, value| sum + value)
+        }
+
+        def factorial(n: u8) -> Option<u128> {
+            (1..n as u128 + 1).fold(Some(1u128), |product, factor| product.and_then(|product| product.checked_mul(factor)))
+        }
+
+        def negate(value: i128) -> Option<i128> {
+            value.checked_neg()
+        }
+
+        def big_factorial(n: u32) -> BigUint {
+            (1..n + 1).fold(BigUint::from(1u32), |product, factor| product * factor)
+        }
+
+        def big_negate(value: BigInt) -> BigInt {
+            -value
+        }
+
+        def widen(byte: i8, short: i16, word: u16) -> i32 {
+            byte as i32 + short as i32 + word as i32
+        }
+
+        #[ruby_nogvl]
+        def sum_of_squares(limit: u64) -> u64 {
+            (1..limit + 1).fold(0u64, |sum, n| sum.wrapping_add(n.wrapping_mul(n)))
+        }
+
+        #[ruby_nogvl]
+        def spin(seconds: f64) -> bool {
+            let deadline = Instant::now() + Duration::from_millis((seconds * 1000.0) as u64);
+
+            while Instant::now() < deadline {
+                if helix::interrupted() {
+                    return false;
+                }
+
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            true
+        }
     }
 
     class Adder {
@@ -23,7 +94,7 @@ ruby! {
             lhs: f64
         }
 
-        def initialize(helix, value: f64) {
+        def initialize(helix, value: f64 = 0.0) {
             Adder { helix, lhs: value }
         }
 
@@ -50,6 +50,28 @@ describe "Console" do
     end
   end
 
+  it "raises Helix::Panic for panics" do
+    expect { console.panic }.to raise_error(Helix::Panic, "raised from Rust with `panic`") { |error|
+      expect(error.location).to match(%r{src/lib\.rs:\d+:\d+\z})
+    }
+  end
+
+  it "only captures the Rust backtrace when RUST_BACKTRACE is set" do
+    expect { console.panic }.to raise_error(Helix::Panic) { |error|
+      if ENV["RUST_BACKTRACE"]
+        expect(error.rust_backtrace).to be_a(String)
+      else
+        expect(error.rust_backtrace).to be_nil
+      end
+    }
+  end
+
+  it "raises errors from raise_panic! as is" do
+    expect { console.raise_panic }.to raise_error(RuntimeError) { |error|
+      expect(error).to_not be_a(Helix::Panic)
+    }
+  end
+
   describe "invalid arguments" do
     it "can handle non-strings" do
       expect { console.log(123) }.to raise_error(TypeError, "Expected a String, got 123")
@@ -65,4 +87,28 @@ describe "Console" do
       expect { console.log(str) }.to raise_error(TypeError, "Expected a valid UTF-8 String, got #{str.inspect}")
     end
   end
+
+  describe "visibility" do
+    it "can define private methods" do
+      expect(Console.private_instance_methods(false)).to include(:secret)
+      expect { console.secret }.to raise_error(NoMethodError)
+      expect(console.send(:secret)).to eq("private")
+    end
+
+    it "can define protected methods" do
+      expect(Console.protected_instance_methods(false)).to include(:shared_secret)
+      expect { console.shared_secret }.to raise_error(NoMethodError)
+      expect(console.instance_eval { shared_secret }).to eq("protected")
+    end
+
+    it "can define private class methods" do
+      expect(Console.singleton_class.private_instance_methods(false)).to include(:class_secret)
+      expect { Console.class_secret }.to raise_error(NoMethodError)
+      expect(Console.send(:class_secret)).to eq("private class method")
+    end
+
+    it "defines other methods as public" do
+      expect(Console.public_instance_methods(false)).to include(:log, :colorize)
+    end
+  end
 end
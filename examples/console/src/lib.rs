@@ -53,5 +53,20 @@ ruby! {
         def panic(&self) {
             panic!("raised from Rust with `panic`");
         }
+
+        #[ruby_visibility = private]
+        def secret(&self) -> String {
+            String::from("private")
+        }
+
+        #[ruby_visibility = protected]
+        def shared_secret(&self) -> String {
+            String::from("protected")
+        }
+
+        #[ruby_visibility = private]
+        def class_secret() -> String {
+            String::from("private class method")
+        }
     }
 }
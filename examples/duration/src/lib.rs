@@ -27,7 +27,7 @@ ruby! {
             value:   i64,
         }
 
-        def initialize(helix, seconds: Option<i32>, minutes: Option<i32>, hours: Option<i32>, days: Option<i32>, weeks: Option<i32>, months: Option<i32>, years: Option<i32>) {
+        def initialize(helix, seconds: Option<i32> = None, minutes: Option<i32> = None, hours: Option<i32> = None, days: Option<i32> = None, weeks: Option<i32> = None, months: Option<i32> = None, years: Option<i32> = None) {
             let mut duration = Duration {
                 helix:   helix,
                 seconds: seconds,
@@ -1,4 +1,7 @@
 require 'spec_helper'
+require 'timeout'
+require 'objspace'
+require 'json'
 
 describe GameOfLife do
   describe '.parse' do
@@ -397,5 +400,45 @@ describe GameOfLife do
       it_should_behave_like 'oscillator', period: 15
     end
   end
+
+  describe '#run!' do
+    it 'should advance the given number of generations' do
+      game = GameOfLife.parse 3, 3, <<~GAME
+        ...
+        ***
+        ...
+      GAME
+
+      expect(game.run!(3)).to eq(3)
+
+      expect(game.to_s).to eq(<<~GAME)
+        .*.
+        .*.
+        .*.
+      GAME
+    end
+
+    it 'can be interrupted' do
+      game = GameOfLife.new(100, 100)
+      game.randomize!
+
+      started = Time.now
+
+      expect(->() { Timeout.timeout(0.1) { game.run!(2**60) } }).to raise_error(Timeout::Error)
+      expect(Time.now - started).to be < 5
+    end
+  end
+
+  describe 'memory usage' do
+    it 'reports the size of the cells' do
+      expect(ObjectSpace.memsize_of(GameOfLife.new(100, 100))).to be >= 100 * 100
+      expect(ObjectSpace.memsize_of(GameOfLife.new(200, 200))).to be >= 200 * 200
+    end
+
+    it 'is wrapped with the Rust type name' do
+      dump = JSON.parse(ObjectSpace.dump(GameOfLife.new(1, 1)))
+      expect(dump['struct']).to eq('game_of_life::GameOfLife')
+    end
+  end
 end
 
@@ -5,11 +5,14 @@ extern crate helix;
 extern crate rand;
 extern crate termion;
 
+use helix::Error;
+
 ruby! {
     class GameOfLife {
         struct {
             width: usize,
             height: usize,
+            #[ruby_memsize]
             cells: Vec<bool>,
         }
 
@@ -90,6 +93,20 @@ ruby! {
             self.cells = next;
         }
 
+        #[ruby_name = "run!"]
+        def run(&mut self, generations: usize) -> Result<usize, Error> {
+            for generation in 0..generations {
+                // Let Ruby stop us (e.g. with Timeout.timeout or Ctrl-C)
+                if generation % 100 == 0 {
+                    helix::check_interrupts()?;
+                }
+
+                self.advance();
+            }
+
+            Ok(generations)
+        }
+
         #[ruby_name = "randomize!"]
         def randomize(&mut self) {
             use rand::prelude::random;
@@ -100,7 +117,7 @@ ruby! {
         }
 
         #[ruby_name = "play!"]
-        def play(&mut self) {
+        def play(&mut self) -> Result<(), Error> {
             use std::io::Write;
             use std::thread::sleep;
             use std::time::Duration;
@@ -149,6 +166,8 @@ ruby! {
 
                 stdout.flush().unwrap();
 
+                let interrupted = helix::check_interrupts();
+
                 match stdin.next() {
                     Some(Ok(Char('r'))) => {
                         self.randomize();
@@ -160,7 +179,16 @@ ruby! {
 
                         stdout.flush().unwrap();
 
-                        return;
+                        return Ok(());
+                    },
+                    _ if interrupted.is_err() => {
+                        print!("{}", clear::All);
+                        print!("{}", Goto(1, 1));
+                        print!("{}", Show);
+
+                        stdout.flush().unwrap();
+
+                        return interrupted;
                     },
                     _ => ()
                 }
@@ -4,10 +4,10 @@
 extern crate helix;
 
 ruby! {
-    class Point {
+    pub class Point {
         struct {
-            x: f64,
-            y: f64
+            pub x: f64,
+            pub y: f64
         }
 
         def initialize(helix, x: f64, y: f64) {
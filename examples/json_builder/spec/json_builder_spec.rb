@@ -85,4 +85,50 @@ describe "JsonBuilder" do
     expect(->{ builder.to_json }).to raise_error(RuntimeError, "Uninitialized JsonBuilder")
     expect(->{ builder.to_h }).to raise_error(RuntimeError, "Uninitialized JsonBuilder")
   end
+
+  describe "Marshal" do
+    it "can be dumped and loaded" do
+      builder["foo"] = "FOO"
+      builder["bar"] = [nil, true, 12345, 1.2345, { "baz" => "BAZ" }]
+
+      copy = Marshal.load(Marshal.dump(builder))
+
+      expect(copy).to be_a(JsonBuilder)
+      expect(copy).not_to equal(builder)
+      expect(copy.to_h).to eq(builder.to_h)
+    end
+
+    it "loads an independent copy" do
+      copy = Marshal.load(Marshal.dump(builder))
+      copy["foo"] = "FOO"
+
+      expect(builder.to_h).to eq({})
+    end
+
+    it "cannot dump a builder once to_json is called" do
+      builder.to_json
+
+      expect(->{ Marshal.dump(builder) }).to raise_error(RuntimeError, "Uninitialized JsonBuilder")
+    end
+
+    it "rejects data from a different format version" do
+      dumped = builder.send(:_dump, -1)
+      dumped.setbyte(0, 2)
+
+      expect(->{ JsonBuilder.send(:_load, dumped) }).to raise_error(TypeError, /unsupported format version 2/)
+      expect(->{ JsonBuilder.send(:_load, "\x02\xFF\xFF\xFF\xFF".b) }).to raise_error(TypeError, /unsupported format version 2/)
+    end
+
+    it "rejects truncated data" do
+      dumped = builder.send(:_dump, -1)
+
+      expect(->{ JsonBuilder.send(:_load, "") }).to raise_error(ArgumentError)
+      expect(->{ JsonBuilder.send(:_load, dumped[0...-1]) }).to raise_error(ArgumentError)
+      expect(->{ JsonBuilder.send(:_load, dumped + "x") }).to raise_error(ArgumentError)
+    end
+
+    it "rejects data with an oversized length prefix" do
+      expect(->{ JsonBuilder.send(:_load, "\x01" + "\xff" * 8) }).to raise_error(ArgumentError)
+    end
+  end
 end
@@ -2,6 +2,9 @@
 
 #[macro_use]
 extern crate helix;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
 extern crate serde_json;
 
 mod coercion;
@@ -10,7 +13,7 @@ use coercion::ToSerde;
 use std::collections::HashMap;
 use std::error::Error;
 
-#[derive(Clone,Debug)]
+#[derive(Clone,Debug,Serialize,Deserialize)]
 pub enum JsonValue {
     Null,
     Boolean(bool),
@@ -22,6 +25,8 @@ pub enum JsonValue {
 }
 
 ruby! {
+    #[ruby_marshal]
+    #[derive(Serialize,Deserialize)]
     pub class JsonBuilder {
         struct {
             entries: HashMap<String, JsonValue>
@@ -0,0 +1,184 @@
+require "spec_helper"
+
+describe "Namespaces" do
+  it "defines a module" do
+    expect(Namespaces).to be_an_instance_of(Module)
+  end
+
+  it "defines module functions" do
+    expect(Namespaces.version).to eq("1.0.0")
+    expect(Namespaces.private_instance_methods).to include(:version)
+  end
+
+  it "can define private module methods" do
+    expect(Namespaces.singleton_class.private_instance_methods(false)).to include(:build_number)
+    expect(Namespaces.private_instance_methods).to_not include(:build_number)
+    expect { Namespaces.build_number }.to raise_error(NoMethodError)
+    expect(Namespaces.send(:build_number)).to eq(42)
+  end
+
+  it "defines classes inside a module" do
+    expect(Namespaces::Counter.name).to eq("Namespaces::Counter")
+    expect(Object.const_defined?(:Counter)).to eq(false)
+
+    counter = Namespaces::Counter.new(1)
+    expect(counter.increment).to eq(2)
+    expect(counter.increment).to eq(3)
+  end
+
+  it "can return nested classes from module functions" do
+    expect(Namespaces.counter(10)).to be_an_instance_of(Namespaces::Counter)
+    expect(Namespaces.counter(10).increment).to eq(11)
+  end
+
+  it "defines nested modules" do
+    expect(Namespaces::Text).to be_an_instance_of(Module)
+    expect(Namespaces::Text.name).to eq("Namespaces::Text")
+    expect(Namespaces::Text.reverse("hello")).to eq("olleh")
+  end
+
+  it "respects #[ruby_name] in nested classes" do
+    expect(Namespaces::Text::Shouter.name).to eq("Namespaces::Text::Shouter")
+    expect(Namespaces::Text::Shouter.shout("hello")).to eq("HELLO")
+  end
+
+  it "defines classes with a built-in superclass" do
+    expect(Namespaces::InvalidCount.superclass).to eq(ArgumentError)
+    expect { raise Namespaces::InvalidCount, "nope" }.to raise_error(ArgumentError, "nope")
+  end
+
+  it "resolves superclasses relative to the enclosing namespace" do
+    expect(Namespaces::Derived.superclass).to eq(Namespaces::Base)
+
+    derived = Namespaces::Derived.new
+    expect(derived.greeting).to eq("hello from base")
+    expect(derived.farewell).to eq("goodbye from derived")
+  end
+
+  it "accepts Ruby subclasses of wrapped classes as arguments" do
+    subclass = Class.new(Namespaces::Counter)
+    counter = subclass.new(5)
+
+    expect(counter.increment).to eq(6)
+    expect(Namespaces.peek(counter)).to eq(6)
+  end
+
+  it "rejects unrelated objects for wrapped arguments" do
+    expect { Namespaces.peek(Namespaces::Base.new) }.to raise_error(TypeError)
+  end
+
+  it "defines exception classes" do
+    expect(Namespaces::CounterError.superclass).to eq(StandardError)
+    expect(Namespaces::Underflow.superclass).to eq(Namespaces::CounterError)
+    expect(ParseError.superclass).to eq(ArgumentError)
+  end
+
+  it "raises declared exceptions from Rust" do
+    counter = Namespaces::Counter.new(1)
+
+    expect(counter.decrement).to eq(0)
+    expect { counter.decrement }.to raise_error(Namespaces::Underflow, "cannot decrement below zero")
+    expect { counter.decrement }.to raise_error(Namespaces::CounterError)
+
+    expect(TopLevel.parse(" 42 ")).to eq(42)
+    expect { TopLevel.parse("forty-two") }.to raise_error(ParseError, 'invalid number: "forty-two"')
+    expect { TopLevel.parse("forty-two") }.to raise_error(ArgumentError)
+  end
+
+  it "poisons objects when a panic interrupts a mutating method" do
+    counter = Namespaces::Counter.new(2**32 - 3)
+
+    expect { counter.increment_by(5) }.to raise_error(Helix::Panic, "counter overflowed")
+
+    message = "This object was poisoned by a panic in Namespaces::Counter#increment_by and can no longer be used"
+    expect { counter.increment }.to raise_error(Helix::PoisonedObjectError, message)
+    expect { counter.check(0) }.to raise_error(Helix::PoisonedObjectError, message)
+    expect { Namespaces.peek(counter) }.to raise_error(Helix::PoisonedObjectError, message)
+  end
+
+  it "does not poison objects after raise_panic! in a mutating method" do
+    counter = Namespaces::Counter.new(1)
+
+    expect { counter.reset_to(5) }.to raise_error(Namespaces::CounterError, "cannot reset to 5")
+    expect(counter.increment).to eq(2)
+    counter.reset_to(0)
+    expect(counter.increment).to eq(1)
+  end
+
+  it "does not poison objects after a panic in a non-mutating method" do
+    counter = Namespaces::Counter.new(1)
+
+    expect { counter.check(2) }.to raise_error(Helix::Panic, /unexpected count/)
+    expect(counter.increment).to eq(2)
+    expect { counter.check(2) }.to_not raise_error
+  end
+
+  it "does not mutate frozen objects" do
+    # FrozenError was added in Ruby 2.5
+    frozen_error = defined?(FrozenError) ? FrozenError : RuntimeError
+
+    counter = Namespaces::Counter.new(1).freeze
+
+    expect { counter.increment }.to raise_error(frozen_error, /can't modify frozen Namespaces::Counter/)
+    expect { counter.finish }.to raise_error(frozen_error, /can't modify frozen Namespaces::Counter/)
+    expect(Namespaces.peek(counter)).to eq(1)
+
+    expect(counter.read).to eq(1)
+    expect(counter.reads).to eq(1)
+  end
+
+  # Ractors were added in Ruby 3.0
+  if defined?(Ractor)
+    it "shares frozen objects of #[ruby_shareable] classes" do
+      point = Point.new(1, 2)
+      Point.mirror(point)
+      expect(Ractor.shareable?(point)).to eq(false)
+
+      expect(Ractor.make_shareable(point)).to equal(point)
+      expect(point).to be_frozen
+      expect(Ractor.shareable?(point)).to eq(true)
+      expect([point.x, point.y]).to eq([2, 1])
+
+      expect { point.move_by(1, 1) }.to raise_error(FrozenError, /can't modify frozen Point/)
+      expect { Point.mirror(point) }.to raise_error(FrozenError, /can't modify frozen Point/)
+      expect([point.x, point.y]).to eq([2, 1])
+    end
+
+    it "does not share objects of other classes" do
+      expect { Ractor.make_shareable(Namespaces::Counter.new(1)) }.to raise_error(Ractor::Error)
+    end
+  end
+
+  it "can consume objects that are not frozen" do
+    expect(Namespaces::Counter.new(3).finish).to eq(3)
+  end
+
+  it "copies objects with dup and clone" do
+    counter = Namespaces::Counter.new(1)
+
+    copy = counter.dup
+    expect(copy.increment).to eq(2)
+    expect(counter.increment).to eq(2)
+    expect(counter.increment).to eq(3)
+    expect(copy.increment).to eq(3)
+
+    frozen = counter.freeze.clone
+    expect(frozen).to be_frozen
+    expect(Namespaces.peek(frozen)).to eq(3)
+
+    thawed = counter.dup
+    expect(thawed).to_not be_frozen
+    expect(thawed.increment).to eq(4)
+  end
+
+  it "does not copy poisoned objects" do
+    counter = Namespaces::Counter.new(2**32 - 1)
+
+    expect { counter.increment_by(1) }.to raise_error(Helix::Panic)
+    expect { counter.dup }.to raise_error(Helix::PoisonedObjectError)
+  end
+
+  it "still defines top-level classes" do
+    expect(TopLevel.namespaced).to eq(false)
+  end
+end
@@ -0,0 +1,167 @@
+#![recursion_limit="1024"]
+
+#[macro_use]
+extern crate helix;
+
+use helix::Error;
+
+ruby! {
+    module Namespaces {
+        def version() -> String {
+            String::from("1.0.0")
+        }
+
+        #[ruby_visibility = private]
+        def build_number() -> u32 {
+            42
+        }
+
+        #[derive(Clone)]
+        class Counter {
+            struct {
+                count: u32,
+                reads: u32
+            }
+
+            def initialize(helix, start: u32) {
+                Counter { helix, count: start, reads: 0 }
+            }
+
+            def increment(&mut self) -> u32 {
+                self.count += 1;
+                self.count
+            }
+
+            def increment_by(&mut self, amount: u32) -> u32 {
+                for _ in 0..amount {
+                    self.count = self.count.checked_add(1).expect("counter overflowed");
+                }
+
+                self.count
+            }
+
+            def reset_to(&mut self, count: u32) {
+                if count > self.count {
+                    raise_panic!(CounterError, format!("cannot reset to {}", count));
+                }
+
+                self.count = count;
+            }
+
+            // Only bookkeeping, so it's fine to call on a frozen counter
+            #[ruby_allow_frozen]
+            def read(&mut self) -> u32 {
+                self.reads += 1;
+                self.count
+            }
+
+            def reads(&self) -> u32 {
+                self.reads
+            }
+
+            def finish(self) -> u32 {
+                self.count
+            }
+
+            def check(&self, expected: u32) {
+                assert_eq!(self.count, expected, "unexpected count");
+            }
+
+            def decrement(&mut self) -> Result<u32, Error> {
+                if self.count == 0 {
+                    raise!(Underflow, "cannot decrement below zero");
+                }
+
+                self.count -= 1;
+                Ok(self.count)
+            }
+        }
+
+        module Text {
+            def reverse(text: String) -> String {
+                text.chars().rev().collect()
+            }
+
+            #[ruby_name = "Shouter"]
+            class TextShouter {
+                def shout(text: String) -> String {
+                    text.to_uppercase()
+                }
+            }
+        }
+
+        def counter(start: u32) -> Counter {
+            Counter::new(start)
+        }
+
+        def peek(counter: &Counter) -> u32 {
+            counter.count
+        }
+
+        exception CounterError;
+        exception Underflow < CounterError;
+
+        class InvalidCount < ArgumentError {
+            def default_message() -> String {
+                String::from("invalid count")
+            }
+        }
+
+        class Base {
+            def greeting(&self) -> String {
+                String::from("hello from base")
+            }
+        }
+
+        class Derived < Base {
+            def farewell(&self) -> String {
+                String::from("goodbye from derived")
+            }
+        }
+    }
+
+    exception ParseError < ArgumentError;
+
+    // Frozen points can be shared with other Ractors
+    #[ruby_shareable]
+    class Point {
+        struct {
+            x: i64,
+            y: i64
+        }
+
+        def initialize(helix, x: i64, y: i64) {
+            Point { helix, x, y }
+        }
+
+        def x(&self) -> i64 {
+            self.x
+        }
+
+        def y(&self) -> i64 {
+            self.y
+        }
+
+        def move_by(&mut self, dx: i64, dy: i64) {
+            self.x += dx;
+            self.y += dy;
+        }
+
+        def mirror(point: &mut Point) {
+            ::std::mem::swap(&mut point.x, &mut point.y);
+        }
+    }
+
+    class TopLevel {
+        def namespaced() -> bool {
+            false
+        }
+
+        def parse(text: String) -> Result<u32, Error> {
+            match text.trim().parse() {
+                Ok(number) => Ok(number),
+                Err(_) => raise!(ParseError, format!("invalid number: {:?}", text))
+            }
+        }
+    }
+}
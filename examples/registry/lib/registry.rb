@@ -0,0 +1,2 @@
+require 'helix_runtime'
+require 'registry/native'
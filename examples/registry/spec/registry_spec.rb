@@ -0,0 +1,239 @@
+require "spec_helper"
+
+describe "Registry" do
+  let(:registry) { Registry.new }
+
+  def fill(registry)
+    3.times do |i|
+      registry.push("item #{i}" * 10)
+      registry.set("key #{i}", "value #{i}" * 10)
+    end
+  end
+
+  def collect_garbage
+    GC.start(full_mark: true, immediate_sweep: true)
+  end
+
+  it "keeps values in a Vec alive" do
+    fill(registry)
+    collect_garbage
+
+    expect(registry.items).to eq(["item 0" * 10, "item 1" * 10, "item 2" * 10])
+  end
+
+  it "keeps values in a HashMap alive" do
+    fill(registry)
+    collect_garbage
+
+    expect(registry.get("key 1")).to eq("value 1" * 10)
+    expect(registry.get("missing")).to be_nil
+  end
+
+  it "keeps values in an Option alive" do
+    expect(registry.last).to be_nil
+
+    fill(registry)
+    collect_garbage
+
+    expect(registry.last).to eq("item 2" * 10)
+  end
+
+  it "keeps values inside nested Helix objects alive" do
+    fill(registry)
+    collect_garbage
+
+    expect(registry.entry_values).to eq(registry.items)
+  end
+
+  it "marks values held by a Helix object directly" do
+    entry = Entry.new(Object.new.tap { |o| o.instance_variable_set(:@tag, "kept") })
+    collect_garbage
+
+    expect(entry.value.instance_variable_get(:@tag)).to eq("kept")
+  end
+
+  it "survives repeated collections under GC stress" do
+    begin
+      GC.stress = true
+      fill(registry)
+    ensure
+      GC.stress = false
+    end
+
+    expect(registry.items.length).to eq(3)
+    expect(registry.get("key 2")).to eq("value 2" * 10)
+  end
+
+  it "can't be copied, as it does not implement Clone" do
+    expect { registry.dup }.to raise_error(TypeError, "Cannot copy Registry, it does not implement Clone")
+    expect { registry.clone }.to raise_error(TypeError, "Cannot copy Registry, it does not implement Clone")
+  end
+
+  it "updates references to values moved by GC.compact" do
+    skip "GC.compact was added in Ruby 2.7" unless GC.respond_to?(:verify_compaction_references)
+
+    fill(registry)
+    registry.on_change { |value| "changed to #{value}" }
+
+    GC.verify_compaction_references(toward: :empty)
+
+    expect(registry.items).to eq(3.times.map { |i| "item #{i}" * 10 })
+    expect(registry.entry_values).to eq(registry.items)
+    expect(registry.last).to eq("item 2" * 10)
+    expect(registry.get("key 2")).to eq("value 2" * 10)
+    expect(registry.notify(1)).to eq("changed to 1")
+  end
+
+  describe "blocks" do
+    before { fill(registry) }
+
+    it "yields to a block" do
+      yielded = []
+      registry.each { |item| yielded << item }
+
+      expect(yielded).to eq(registry.items)
+    end
+
+    it "yields multiple values" do
+      yielded = []
+      registry.each_with_index { |item, index| yielded << [item, index] }
+
+      expect(yielded).to eq(registry.items.each_with_index.to_a)
+    end
+
+    it "raises LocalJumpError when a required block is missing" do
+      expect { registry.each }.to raise_error(LocalJumpError, "no block given")
+    end
+
+    it "supports optional blocks" do
+      expect(registry.count).to eq(3)
+      expect(registry.count { |item| item.start_with?("item 1") }).to eq(1)
+    end
+
+    it "raises a TypeError when the block returns the wrong type" do
+      expect { registry.count { |item| item } }.to raise_error(TypeError)
+    end
+
+    it "propagates exceptions raised in the block" do
+      error = Class.new(StandardError)
+
+      expect { registry.each { |item| raise error, "boom" } }.to raise_error(error, "boom")
+    end
+
+    it "supports break" do
+      yielded = []
+      result = registry.each { |item| yielded << item; break :stopped }
+
+      expect(result).to eq(:stopped)
+      expect(yielded.length).to eq(1)
+    end
+
+    it "supports throw" do
+      result = catch(:done) do
+        registry.each { |item| throw :done, item }
+        :not_reached
+      end
+
+      expect(result).to eq("item 0" * 10)
+    end
+
+    it "allows reading the registry from a block" do
+      lengths = []
+      registry.each { |item| lengths << registry.items.length }
+
+      expect(lengths).to eq([3, 3, 3])
+    end
+
+    it "raises Helix::BorrowError when a block mutates the registry it is iterating" do
+      expect { registry.each { |item| registry.push(item) } }.to raise_error(Helix::BorrowError, "Cannot borrow Registry, it is already borrowed")
+      expect(registry.items.length).to eq(3)
+
+      # The borrows are released once the method returns
+      expect(registry.push("item 3")).to eq(4)
+    end
+
+    it "raises Helix::BorrowError when a callback reads an object being mutated" do
+      registry.on_change { |value| registry.items }
+      expect { registry.notify_all("value") }.to raise_error(Helix::BorrowError, "Cannot borrow Registry, it is already mutably borrowed")
+      expect(registry.items.length).to eq(3)
+    end
+
+    it "raises Helix::BorrowError when an object is moved and borrowed by the same call" do
+      expect { registry.merge(registry) }.to raise_error(Helix::BorrowError, "Cannot borrow Registry, it is already mutably borrowed")
+      expect(registry.items.length).to eq(3)
+
+      other = Registry.new
+      other.push("item 3")
+      expect(registry.merge(other)).to eq(4)
+      expect(other.items).to eq(["item 3"])
+    end
+
+    it "can keep a block around as a proc" do
+      registry.on_change { |value| "changed to #{value}" }
+      collect_garbage
+
+      expect(registry.callback).to be_a(Proc)
+      expect(registry.callback.call(1)).to eq("changed to 1")
+    end
+  end
+
+  describe "calling Ruby methods" do
+    let(:registry) { Registry.new }
+
+    it "calls methods on Ruby objects" do
+      registry.push("one")
+      registry.push(:two)
+      registry.push(3)
+
+      expect(registry.inspect_items).to eq(['"one"', ":two", "3"])
+    end
+
+    it "passes arguments and converts the result" do
+      expect(registry.notify(1)).to be_nil
+
+      registry.on_change { |value| "changed to #{value}" }
+
+      expect(registry.notify(1)).to eq("changed to 1")
+    end
+
+    it "raises a TypeError when the result has the wrong type" do
+      registry.push(Class.new { def inspect; 42; end }.new)
+
+      expect { registry.inspect_items }.to raise_error(TypeError)
+    end
+
+    it "propagates the original exception" do
+      error = Class.new(StandardError)
+      original = error.new("boom")
+      registry.on_change { |value| raise original }
+
+      expect { registry.notify(1) }.to raise_error { |raised| expect(raised).to equal(original) }
+    end
+
+    it "keeps the backtrace, cause and attributes of the original exception" do
+      error = Class.new(StandardError) { attr_accessor :code }
+
+      registry.on_change do |value|
+        begin
+          raise "inner"
+        rescue
+          exception = error.new("outer")
+          exception.code = value
+          raise exception
+        end
+      end
+
+      expect { registry.notify(42) }.to raise_error(error, "outer") { |raised|
+        expect(raised.code).to eq(42)
+        expect(raised.cause.message).to eq("inner")
+        expect(raised.backtrace.first).to include(__FILE__)
+      }
+    end
+
+    it "supports throw" do
+      registry.on_change { |value| throw :done, value }
+
+      expect(catch(:done) { registry.notify(1); :not_reached }).to eq(1)
+    end
+  end
+end
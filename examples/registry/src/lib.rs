@@ -0,0 +1,142 @@
+#![recursion_limit="1024"]
+
+#[macro_use]
+extern crate helix;
+
+use helix::{Error, Funcall};
+use helix::sys::VALUE;
+use std::collections::HashMap;
+
+ruby! {
+    class Entry {
+        struct {
+            #[ruby_mark]
+            value: VALUE
+        }
+
+        def initialize(helix, value: VALUE) {
+            Entry { helix, value }
+        }
+
+        def value(&self) -> VALUE {
+            self.value
+        }
+    }
+
+    class Registry {
+        struct {
+            #[ruby_mark]
+            items: Vec<VALUE>,
+            #[ruby_mark]
+            named: HashMap<String, VALUE>,
+            #[ruby_mark]
+            last: Option<VALUE>,
+            #[ruby_mark]
+            entries: Vec<Entry>,
+            #[ruby_mark]
+            callback: Option<VALUE>,
+            pushes: usize
+        }
+
+        def initialize(helix) {
+            Registry {
+                helix,
+                items: Vec::new(),
+                named: HashMap::new(),
+                last: None,
+                entries: Vec::new(),
+                callback: None,
+                pushes: 0
+            }
+        }
+
+        def push(&mut self, value: VALUE) -> usize {
+            self.items.push(value);
+            self.last = Some(value);
+            self.entries.push(Entry::new(value));
+            self.pushes += 1;
+            self.pushes
+        }
+
+        def merge(self, other: &Registry) -> usize {
+            self.items.len() + other.items.len()
+        }
+
+        def set(&mut self, name: String, value: VALUE) {
+            self.named.insert(name, value);
+        }
+
+        def get(&self, name: String) -> Option<VALUE> {
+            self.named.get(&name).cloned()
+        }
+
+        def items(&self) -> Vec<VALUE> {
+            self.items.clone()
+        }
+
+        def last(&self) -> Option<VALUE> {
+            self.last
+        }
+
+        def entry_values(&self) -> Vec<VALUE> {
+            self.entries.iter().map(|entry| entry.value).collect()
+        }
+
+        def each(&self, block: Block) -> Result<(), Error> {
+            for item in &self.items {
+                block.call::<VALUE>((*item,))?;
+            }
+
+            Ok(())
+        }
+
+        def each_with_index(&self, block: Block) -> Result<(), Error> {
+            for (index, item) in self.items.iter().enumerate() {
+                block.call::<VALUE>((*item, index))?;
+            }
+
+            Ok(())
+        }
+
+        def count(&self, block: Option<Block>) -> Result<usize, Error> {
+            match block {
+                None => Ok(self.items.len()),
+                Some(block) => {
+                    let mut count = 0;
+
+                    for item in &self.items {
+                        if block.call::<bool>((*item,))? {
+                            count += 1;
+                        }
+                    }
+
+                    Ok(count)
+                }
+            }
+        }
+
+        def on_change(&mut self, block: Block) {
+            self.callback = Some(block.to_proc());
+        }
+
+        def callback(&self) -> Option<VALUE> {
+            self.callback
+        }
+
+        def notify(&self, value: VALUE) -> Result<Option<VALUE>, Error> {
+            match self.callback {
+                Some(callback) => callback.call("call", (value,)).map(Some),
+                None => Ok(None)
+            }
+        }
+
+        def notify_all(&mut self, value: VALUE) -> Result<Option<VALUE>, Error> {
+            self.last = Some(value);
+            self.notify(value)
+        }
+
+        def inspect_items(&self) -> Result<Vec<String>, Error> {
+            self.items.iter().map(|item| item.call("inspect", ())).collect()
+        }
+    }
+}
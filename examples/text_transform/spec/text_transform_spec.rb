@@ -60,4 +60,101 @@ describe "TextTransform" do
       "ǝʌolɹǝpuǝʇ@": "ǝlpuɐɥ"
     })
   end
+
+  it "can borrow string arguments" do
+    expect(TextTransform.count_lines("one\ntwo\nthree\n")).to eq(3)
+    expect(TextTransform.count_lines("\xFF\n\xFE\n".b)).to eq(2)
+    expect(TextTransform.widen("Hello".freeze)).to eq("Ｈｅｌｌｏ")
+    expect { TextTransform.widen("\xFF".b) }.to raise_error(TypeError)
+    expect { TextTransform.count_lines(:one) }.to raise_error(TypeError)
+  end
+
+  it "can take and return binary strings" do
+    data = "\x00\xFF\x10 binary".b
+    encoded = TextTransform.xor(data, 0xAA)
+
+    expect(encoded.encoding).to eq(Encoding::ASCII_8BIT)
+    expect(encoded.bytes).to eq(data.bytes.map { |byte| byte ^ 0xAA })
+    expect(TextTransform.xor(encoded, 0xAA)).to eq(data)
+
+    expect(TextTransform.xor("héllo", 0).bytes).to eq("héllo".bytes)
+    expect(TextTransform.xor("hi".encode("UTF-16LE"), 0).bytes).to eq([104, 0, 105, 0])
+    expect(TextTransform.xor("\xFF", 0).bytes).to eq([255])
+    expect { TextTransform.xor(:data, 0) }.to raise_error(TypeError)
+  end
+
+  it "can convert bytes::Bytes to and from binary strings" do
+    data = "\x89PNG\r\n\x1A\n\x00\x00".b
+    header = TextTransform.header(data, 8)
+
+    expect(header.encoding).to eq(Encoding::ASCII_8BIT)
+    expect(header).to eq("\x89PNG\r\n\x1A\n".b)
+    expect(TextTransform.header("héllo", 3).bytes).to eq("héllo".bytes.first(3))
+    expect(TextTransform.header("hi", 8)).to eq("hi".b)
+    expect { TextTransform.header(:data, 1) }.to raise_error(TypeError)
+  end
+
+  it "can transcode strings to UTF-8" do
+    expect(TextTransform.widen_any("Hello")).to eq("Ｈｅｌｌｏ")
+    expect(TextTransform.widen_any("Hello 日本".encode("Shift_JIS"))).to eq("Ｈｅｌｌｏ　日本")
+    expect(TextTransform.widen_any("café".encode("ISO-8859-1"))).to eq("ｃａｆé")
+    expect(TextTransform.widen_any("Hi".encode("UTF-16LE"))).to eq("Ｈｉ")
+
+    expect { TextTransform.widen("日本".encode("Shift_JIS")) }.to raise_error(TypeError)
+    expect { TextTransform.widen_any("\xFF".force_encoding("Shift_JIS")) }.to raise_error(TypeError)
+    expect { TextTransform.widen_any("\xFF") }.to raise_error(TypeError)
+    expect { TextTransform.widen_any("\xFF".b) }.to raise_error(TypeError)
+  end
+
+  it "can replace invalid bytes when transcoding" do
+    expect(TextTransform.scrub("abc\xFFdef")).to eq("abc\uFFFDdef")
+    expect(TextTransform.scrub("日本".encode("Shift_JIS"))).to eq("日本")
+    expect(TextTransform.scrub("a\xFFb".force_encoding("Shift_JIS"))).to eq("a\uFFFDb")
+    expect(TextTransform.scrub("a\xFFb".b)).to eq("a\uFFFDb")
+    expect(TextTransform.scrub("abc").encoding).to eq(Encoding::UTF_8)
+  end
+
+  it "can round-trip strings in any encoding" do
+    text = "日本".encode("Shift_JIS")
+    repeated = TextTransform.repeat(text, 2)
+
+    expect(repeated.encoding).to eq(Encoding::Shift_JIS)
+    expect(repeated).to eq(text * 2)
+
+    invalid = "\xFF".force_encoding("EUC-JP")
+    repeated = TextTransform.repeat(invalid, 3)
+
+    expect(repeated.encoding).to eq(Encoding::EUC_JP)
+    expect(repeated.bytes).to eq([255, 255, 255])
+
+    expect(TextTransform.repeat("ab".b, 2).encoding).to eq(Encoding::ASCII_8BIT)
+  end
+
+  it "locks borrowed strings until the call returns" do
+    text = +"Hello Aaron"
+    words = []
+
+    TextTransform.each_word(text) { |word| words << word }
+    expect(words).to eq(%w"Hello Aaron")
+
+    expect { TextTransform.each_word(text) { text << "!" } }.to raise_error(RuntimeError, /temporarily locked/)
+    expect { TextTransform.each_word(text) { text.replace("") } }.to raise_error(RuntimeError, /temporarily locked/)
+
+    text << "!"
+    expect(text).to eq("Hello Aaron!")
+  end
+
+  it "keeps borrowed strings alive until the call returns" do
+    # Long enough not to be embedded in the String object
+    words = %w"Hello Aaron".map { |word| word * 100 }
+    expected = words.join(" ")
+
+    joined = TextTransform.join_after(words) do
+      words.clear
+      GC.start
+      100.times.map { "x" * 1000 }
+    end
+
+    expect(joined).to eq(expected)
+  end
 end
@@ -2,13 +2,16 @@
 
 #[macro_use]
 extern crate helix;
+extern crate bytes;
 
+use bytes::Bytes;
 use std::collections::HashMap;
-use helix::Symbol;
+use helix::{Binary, EncodedString, Error, LossyString, Symbol, TranscodedString};
+use helix::sys::VALUE;
 
 ruby! {
     class TextTransform {
-        def widen(text: String) -> String {
+        def widen(text: &str) -> String {
             text.chars().map(|char| {
                 match char {
                     ' ' => '\u{3000}', '!' => '！', '"' => '＂', '#' => '＃', '$' => '＄', '%' => '％', '&' => '＆', '\'' => '＇',
@@ -28,14 +31,14 @@ ruby! {
         }
 
         def widen_array(text: Vec<String>) -> Vec<String> {
-            text.into_iter().map(TextTransform::widen).collect()
+            text.iter().map(|text| TextTransform::widen(text)).collect()
         }
 
         def widen_hash(text: HashMap<Symbol, String>) -> HashMap<Symbol, String> {
-            text.into_iter().map(|(k,v)| (Symbol::from_string(TextTransform::widen(k.to_string())), TextTransform::widen(v))).collect()
+            text.into_iter().map(|(k,v)| (Symbol::from_string(TextTransform::widen(&k.to_string())), TextTransform::widen(&v))).collect()
         }
 
-        def narrowen(text: String) -> String {
+        def narrowen(text: &str) -> String {
             text.chars().map(|char| {
                 match char {
                     '\u{3000}' => ' ', '！' => '!', '＂' => '"', '＃' => '#', '＄' => '$', '％' => '%', '＆' => '&', '＇' => '\'',
@@ -55,14 +58,14 @@ ruby! {
         }
 
         def narrowen_array(text: Vec<String>) -> Vec<String> {
-            text.into_iter().map(TextTransform::narrowen).collect()
+            text.iter().map(|text| TextTransform::narrowen(text)).collect()
         }
 
         def narrowen_hash(text: HashMap<Symbol, String>) -> HashMap<Symbol, String> {
-            text.into_iter().map(|(k,v)| (Symbol::from_string(TextTransform::narrowen(k.to_string())), TextTransform::narrowen(v))).collect()
+            text.into_iter().map(|(k,v)| (Symbol::from_string(TextTransform::narrowen(&k.to_string())), TextTransform::narrowen(&v))).collect()
         }
 
-        def flip(text: String) -> String {
+        def flip(text: &str) -> String {
             text.chars().rev().map(|char| {
                 match char {
                     '!' => '¡', '"' => '„', '&' => '⅋', '\'' => '‚', '(' => ')', ')' => '(', ',' => '‘', '.' => '˙',
@@ -81,11 +84,48 @@ ruby! {
         }
 
         def flip_array(text: Vec<String>) -> Vec<String> {
-            text.into_iter().rev().map(TextTransform::flip).collect()
+            text.iter().rev().map(|text| TextTransform::flip(text)).collect()
         }
 
         def flip_hash(text: HashMap<Symbol, String>) -> HashMap<Symbol, String> {
-            text.into_iter().map(|(k,v)| (Symbol::from_string(TextTransform::flip(v)), TextTransform::flip(k.to_string()))).collect()
+            text.into_iter().map(|(k,v)| (Symbol::from_string(TextTransform::flip(&v)), TextTransform::flip(&k.to_string()))).collect()
+        }
+
+        def widen_any(text: TranscodedString) -> String {
+            TextTransform::widen(&text)
+        }
+
+        def scrub(text: LossyString) -> String {
+            text.into_string()
+        }
+
+        def repeat(text: EncodedString, times: usize) -> EncodedString {
+            EncodedString::new(text.bytes().repeat(times), text.encoding_index())
+        }
+
+        def count_lines(bytes: &[u8]) -> usize {
+            bytes.iter().filter(|&&byte| byte == b'\n').count()
+        }
+
+        def xor(data: Binary, key: u8) -> Binary {
+            data.iter().map(|byte| byte ^ key).collect::<Vec<u8>>().into()
+        }
+
+        def header(data: Bytes, len: usize) -> Bytes {
+            data.slice(..len.min(data.len()))
+        }
+
+        def each_word(text: &str, block: Block) -> Result<(), Error> {
+            for word in text.split_whitespace() {
+                block.call::<VALUE>((word,))?;
+            }
+
+            Ok(())
+        }
+
+        def join_after(words: Vec<&str>, block: Block) -> Result<String, Error> {
+            block.call::<VALUE>(())?;
+            Ok(words.join(" "))
         }
     }
 }
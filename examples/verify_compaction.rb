@@ -0,0 +1,10 @@
+# Loaded into the specs of every example by `rake test_compaction`. Moves
+# every object that can be moved after each spec, while the objects it used
+# are still alive, and checks that nothing points to their old locations.
+RSpec.configure do |config|
+  config.after(:each) do
+    if GC.respond_to?(:verify_compaction_references)
+      GC.verify_compaction_references(toward: :empty)
+    end
+  end
+end
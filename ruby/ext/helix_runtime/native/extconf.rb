@@ -4,4 +4,11 @@ if RUBY_PLATFORM =~ /mingw/
   system "rake native_def_file"
 end
 
+# GC compaction was added in Ruby 2.7
+have_func "rb_gc_location"
+have_func "rb_gc_mark_movable"
+
+# Keyword argument flags were added in Ruby 2.7
+have_func "rb_keyword_given_p"
+
 create_makefile "helix_runtime/native"
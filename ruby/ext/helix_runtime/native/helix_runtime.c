@@ -7,7 +7,7 @@
 #include <helix_runtime.h>
 
 // Update with version.rb
-const char* HELIX_RUNTIME_VERSION = "0.7.5";
+const char* HELIX_RUNTIME_VERSION = "0.8.0";
 
 const char* HELIX_PRIsVALUE = PRIsVALUE;
 const char* HELIX_SPRINTF_TO_S = "%" PRIsVALUE;
@@ -121,10 +121,37 @@ VALUE HELIX_F642NUM(RUST_F64 num) {
   return DBL2NUM(num);
 }
 
+// Without compaction, objects never move and marking always pins them
+VALUE HELIX_rb_gc_location(VALUE value) {
+#ifdef HAVE_RB_GC_LOCATION
+  return rb_gc_location(value);
+#else
+  return value;
+#endif
+}
+
+void HELIX_rb_gc_mark_movable(VALUE value) {
+#ifdef HAVE_RB_GC_MARK_MOVABLE
+  rb_gc_mark_movable(value);
+#else
+  rb_gc_mark(value);
+#endif
+}
+
 bool HELIX_OBJ_FROZEN(VALUE obj) {
   return OBJ_FROZEN(obj);
 }
 
+// Before Ruby 2.7 a trailing Hash is passed as keywords whenever the method
+// accepts them
+bool HELIX_rb_keyword_given_p(void) {
+#ifdef HAVE_RB_KEYWORD_GIVEN_P
+  return rb_keyword_given_p();
+#else
+  return true;
+#endif
+}
+
 void* HELIX_Data_Get_Struct_Value(VALUE obj) {
   void* data;
   Data_Get_Struct(obj, void*, data);
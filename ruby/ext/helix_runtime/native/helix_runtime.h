@@ -44,8 +44,13 @@ HELIX_EXTERN VALUE HELIX_I322NUM(RUST_I32);
 HELIX_EXTERN RUST_F64 HELIX_NUM2F64(VALUE);
 HELIX_EXTERN VALUE HELIX_F642NUM(RUST_F64);
 
+HELIX_EXTERN VALUE HELIX_rb_gc_location(VALUE value);
+HELIX_EXTERN void HELIX_rb_gc_mark_movable(VALUE value);
+
 HELIX_EXTERN bool HELIX_OBJ_FROZEN(VALUE obj);
 
+HELIX_EXTERN bool HELIX_rb_keyword_given_p(void);
+
 HELIX_EXTERN VALUE HELIX_Qtrue;
 HELIX_EXTERN VALUE HELIX_Qfalse;
 HELIX_EXTERN VALUE HELIX_Qnil;
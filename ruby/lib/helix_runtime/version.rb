@@ -1,5 +1,5 @@
 module HelixRuntime
   # Also update helix_runtime.c
-  VERSION = "0.7.5"
+  VERSION = "0.8.0"
   GEM_VERSION = VERSION.gsub("-", ".")
 end
@@ -0,0 +1,120 @@
+use sys::{self, VALUE, RB_TYPE_P, T_HASH, rb_hash_foreach, void, st_retval};
+use super::{Class, Error, FromRuby, Symbol, ToError};
+
+/// Checks the number of arguments passed to a method that takes optional or
+/// splat arguments, raising the same `ArgumentError` Ruby itself would.
+pub fn check_arity(given: usize, required: usize, optional: usize, rest: bool) -> Result<(), Error> {
+    if given >= required && (rest || given <= required + optional) {
+        return Ok(());
+    }
+
+    let expected = if rest {
+        format!("{}+", required)
+    } else if optional == 0 {
+        format!("{}", required)
+    } else {
+        format!("{}..{}", required, required + optional)
+    };
+
+    raise!(unsafe { Class::from_value(sys::rb_eArgError) }, format!("wrong number of arguments (given {}, expected {})", given, expected));
+}
+
+/// The keyword arguments passed to a method that declares `#[kw]` arguments.
+pub struct Keywords {
+    values: Vec<(Symbol, VALUE)>
+}
+
+impl Keywords {
+    /// Splits the keyword arguments off `args`.
+    ///
+    /// `keywords` lists the method's arguments as `Some((name, required))`
+    /// for keyword arguments and `None` for positional ones. Only a trailing
+    /// `Hash` passed as keywords (`foo(a: 1)`, not `foo({ a: 1 })`) is split
+    /// off; before Ruby 2.7 every trailing `Hash` of `Symbol` keys is.
+    pub fn extract<'a>(args: &'a [VALUE], keywords: &[Option<(&str, bool)>]) -> (&'a [VALUE], Keywords) {
+        let mut result = Keywords { values: vec![] };
+
+        if keywords.iter().all(Option::is_none) || !unsafe { sys::rb_keyword_given_p() } {
+            return (args, result);
+        }
+
+        let args = match args.split_last() {
+            Some((&last, rest)) => match keyword_pairs(last) {
+                Some(pairs) => {
+                    result.values = pairs;
+                    rest
+                },
+                None => args
+            },
+            None => args
+        };
+
+        (args, result)
+    }
+
+    /// Checks the keywords split off by `extract`, once the arity of the
+    /// positional arguments has been checked. Missing required keywords and
+    /// unknown keywords raise the same `ArgumentError` Ruby itself would.
+    pub fn check(&self, keywords: &[Option<(&str, bool)>]) -> Result<(), Error> {
+        let missing: Vec<&str> = keywords.iter()
+            .filter_map(|keyword| *keyword)
+            .filter(|&(name, required)| required && self.get(name).is_none())
+            .map(|(name, _)| name)
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(keyword_error("missing", missing));
+        }
+
+        let known: Vec<Symbol> = keywords.iter()
+            .filter_map(|keyword| *keyword)
+            .map(|(name, _)| Symbol::from_string(name.to_string()))
+            .collect();
+
+        let unknown: Vec<String> = self.values.iter()
+            .filter(|&&(key, _)| !known.contains(&key))
+            .map(|&(key, _)| key.to_string())
+            .collect();
+
+        if !unknown.is_empty() {
+            return Err(keyword_error("unknown", unknown.iter().map(String::as_str).collect()));
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<VALUE> {
+        let key = Symbol::from_string(name.to_string());
+        self.values.iter().find(|&&(k, _)| k == key).map(|&(_, v)| v)
+    }
+}
+
+// Collects the pairs of a `Hash` with only `Symbol` keys, in insertion order,
+// so errors list keywords in the order they were passed.
+fn keyword_pairs(value: VALUE) -> Option<Vec<(Symbol, VALUE)>> {
+    if !unsafe { RB_TYPE_P(value, T_HASH) } {
+        return None;
+    }
+
+    let mut pairs = Vec::<(VALUE, VALUE)>::new();
+    unsafe { rb_hash_foreach(value, collect_pair, &mut pairs as *mut Vec<(VALUE, VALUE)> as *mut void) };
+
+    pairs.into_iter()
+        .map(|(k, v)| Symbol::from_ruby(k).ok().map(|k| (Symbol::from_checked(k), v)))
+        .collect()
+}
+
+extern "C" fn collect_pair(key: VALUE, value: VALUE, vec: *mut void) -> st_retval {
+    let vec = unsafe { &mut *(vec as *mut Vec<(VALUE, VALUE)>) };
+    vec.push((key, value));
+    st_retval::ST_CONTINUE
+}
+
+fn keyword_error(kind: &str, names: Vec<&str>) -> Error {
+    let plural = if names.len() == 1 { "" } else { "s" };
+    let names: Vec<String> = names.iter().map(|name| format!(":{}", name)).collect();
+
+    format!("{} keyword{}: {}", kind, plural, names.join(", "))
+        .to_error()
+        .with_class(unsafe { Class::from_value(sys::rb_eArgError) })
+}
@@ -0,0 +1,51 @@
+use std::ops::{Deref, DerefMut};
+
+/// The bytes of a Ruby String, for blobs that aren't text.
+///
+/// Any String converts to a `Binary` regardless of its encoding, without
+/// checking that the bytes are valid. A `Binary` converts back to Ruby as a
+/// binary (ASCII-8BIT) String.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Binary(pub Vec<u8>);
+
+impl Binary {
+    pub fn new() -> Binary {
+        Binary(Vec::new())
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for Binary {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl DerefMut for Binary {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for Binary {
+    fn from(bytes: Vec<u8>) -> Binary {
+        Binary(bytes)
+    }
+}
+
+impl<'a> From<&'a [u8]> for Binary {
+    fn from(bytes: &'a [u8]) -> Binary {
+        Binary(bytes.to_vec())
+    }
+}
+
+impl From<Binary> for Vec<u8> {
+    fn from(binary: Binary) -> Vec<u8> {
+        binary.0
+    }
+}
@@ -0,0 +1,57 @@
+use std::marker::PhantomData;
+use sys::{self, VALUE};
+use super::{Class, Error, FromRuby, ToRubyArgs, protect};
+
+/// The block passed to the currently running Helix method.
+///
+/// Declare it as the last argument of a method, either as `Block` (calling
+/// the method without a block raises `LocalJumpError`) or as `Option<Block>`.
+/// `ruby!` recognizes the name itself, so it doesn't need to be imported.
+/// A `Block` is only valid while that method is running; use `to_proc` to
+/// keep the block around for longer.
+#[derive(Debug)]
+pub struct Block {
+    // Blocks belong to the current Ruby frame, they can't be sent to other threads
+    marker: PhantomData<*mut ()>
+}
+
+impl Block {
+    #[doc(hidden)]
+    pub fn given() -> Option<Block> {
+        if unsafe { sys::rb_block_given_p() } {
+            Some(Block { marker: PhantomData })
+        } else {
+            None
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn required() -> Result<Block, Error> {
+        match Block::given() {
+            Some(block) => Ok(block),
+            None => raise!(unsafe { Class::from_value(sys::rb_eLocalJumpError) }, "no block given")
+        }
+    }
+
+    /// Yields `args` to the block and converts its result.
+    ///
+    /// `args` is a tuple (use `()` for no arguments and `(value,)` for one).
+    /// Exceptions raised in the block, as well as `break` and `throw`, are
+    /// returned as an `Err` which resumes them when it reaches Ruby.
+    pub fn call<T: FromRuby>(&self, args: impl ToRubyArgs) -> Result<T, Error> {
+        let args = args.to_ruby_args()?;
+        let args = args.as_ref();
+
+        let result = protect(|| unsafe {
+            sys::rb_yield_values2(args.len() as ::libc::c_int, args.as_ptr())
+        })?;
+
+        T::from_ruby(result).map(T::from_checked)
+    }
+
+    /// Converts the block into a `Proc` that can outlive the current method
+    /// call, e.g. to store it in a `#[ruby_mark]` field.
+    pub fn to_proc(&self) -> VALUE {
+        unsafe { sys::rb_block_proc() }
+    }
+}
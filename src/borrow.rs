@@ -0,0 +1,236 @@
+use std::cell::{Cell, RefCell, UnsafeCell};
+use std::ffi::CStr;
+use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use sys::{self, VALUE};
+use super::{Class, Error, Mark, ToError};
+use super::string_borrow::{borrowed_strings, release_strings};
+
+// The borrow state of a wrapped struct: the number of shared borrows, or
+// MUTABLY_BORROWED. It is atomic because frozen `#[ruby_shareable]` objects
+// can be borrowed from several Ractors at once.
+type BorrowFlag = AtomicIsize;
+
+const UNBORROWED: isize = 0;
+const MUTABLY_BORROWED: isize = -1;
+
+static mut BORROW_ERROR_CLASS: Option<Class> = None;
+
+thread_local! {
+    // The borrows taken by the Helix method calls running on this thread,
+    // which are released when the call that took them returns
+    static HELD: RefCell<Vec<*const BorrowFlag>> = const { RefCell::new(Vec::new()) };
+    static SCOPES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Defines `Helix::BorrowError`.
+pub fn install() {
+    unsafe {
+        let helix = sys::rb_define_module(cstr!("Helix"));
+        let class = sys::rb_define_class_under(helix, cstr!("BorrowError"), sys::rb_eRuntimeError);
+        BORROW_ERROR_CLASS = Some(Class::from_value(class));
+    }
+}
+
+/// A struct wrapped in a Ruby object, along with `RefCell`-style borrow
+/// tracking, so that reentrant calls (a method calling back into Ruby, which
+/// calls another method on the same object) can't create aliasing `&mut`s.
+pub struct Wrapped<T> {
+    borrow: BorrowFlag,
+    value: UnsafeCell<T>
+}
+
+impl<T> Wrapped<T> {
+    pub fn new(value: T) -> Wrapped<T> {
+        Wrapped { borrow: AtomicIsize::new(UNBORROWED), value: UnsafeCell::new(value) }
+    }
+
+    pub fn get(&self) -> &T {
+        unsafe { &*self.value.get() }
+    }
+
+    /// Updates the struct's references to Ruby objects moved by `GC.compact`.
+    ///
+    /// This runs in the middle of a GC, which can happen while a method that
+    /// called back into Ruby still borrows the struct, so it has to bypass
+    /// the borrow flag.
+    ///
+    /// # Safety
+    ///
+    /// Only call this from the `dcompact` callback of the wrapping object.
+    pub unsafe fn compact(&self) where T: Mark {
+        Mark::compact(&mut *self.value.get());
+    }
+
+    /// Borrows the struct wrapped in `object` until the current `BorrowScope`
+    /// ends.
+    ///
+    /// # Safety
+    ///
+    /// `wrapped` must be the live data pointer of `object`, and the returned
+    /// reference must not outlive the current `BorrowScope`.
+    pub unsafe fn borrow<'a>(wrapped: *mut Wrapped<T>, object: VALUE) -> Result<&'a T, Error> {
+        let wrapped = &*wrapped;
+
+        if in_scope() {
+            let shared = wrapped.borrow.fetch_update(Acquire, Relaxed, |count| {
+                if count == MUTABLY_BORROWED { None } else { Some(count + 1) }
+            });
+
+            if shared.is_err() {
+                return Err(borrow_error(object, "it is already mutably borrowed"));
+            }
+
+            hold(&wrapped.borrow);
+        } else if wrapped.borrow.load(Acquire) == MUTABLY_BORROWED {
+            return Err(borrow_error(object, "it is already mutably borrowed"));
+        }
+
+        Ok(&*wrapped.value.get())
+    }
+
+    /// Mutably borrows the struct wrapped in `object` until the current
+    /// `BorrowScope` ends.
+    ///
+    /// # Safety
+    ///
+    /// Same as `borrow`.
+    pub unsafe fn borrow_mut<'a>(wrapped: *mut Wrapped<T>, object: VALUE) -> Result<&'a mut T, Error> {
+        let wrapped = &*wrapped;
+
+        if in_scope() {
+            if let Err(count) = wrapped.borrow.compare_exchange(UNBORROWED, MUTABLY_BORROWED, Acquire, Relaxed) {
+                return Err(borrowed_error(object, count));
+            }
+
+            hold(&wrapped.borrow);
+        } else {
+            wrapped.check_unborrowed(object)?;
+        }
+
+        Ok(&mut *wrapped.value.get())
+    }
+
+    /// Reserves the struct wrapped in `object` to be moved out with `take`.
+    ///
+    /// The struct counts as mutably borrowed until then, so that it can't
+    /// be borrowed again in the meantime, e.g. when the same object is
+    /// passed as another argument.
+    ///
+    /// # Safety
+    ///
+    /// `wrapped` must be the live data pointer of `object`.
+    pub unsafe fn reserve(wrapped: *mut Wrapped<T>, object: VALUE) -> Result<(), Error> {
+        Wrapped::borrow_mut(wrapped, object).map(|_| ())
+    }
+
+    /// Moves the struct out of a `Wrapped` reserved with `reserve` and frees
+    /// it.
+    ///
+    /// # Safety
+    ///
+    /// `wrapped` must have been reserved with `reserve`, and
+    /// the caller has to detach it from its Ruby object.
+    pub unsafe fn take(wrapped: *mut Wrapped<T>) -> T {
+        let wrapped = Box::from_raw(wrapped);
+        unhold(&wrapped.borrow);
+        wrapped.value.into_inner()
+    }
+
+    /// Checks that the struct can be moved out of `object`.
+    pub fn check_unborrowed(&self, object: VALUE) -> Result<(), Error> {
+        match self.borrow.load(Acquire) {
+            UNBORROWED => Ok(()),
+            count => Err(borrowed_error(object, count))
+        }
+    }
+}
+
+/// Releases the borrows taken while it was alive.
+///
+/// The generated glue creates one at the start of every Helix method call
+/// (including `initialize`, `initialize_copy` and `_load`), before converting
+/// the arguments. Borrows of wrapped structs, and of strings borrowed as
+/// `&str` or `&[u8]`, taken during the call last until it returns. Outside of
+/// a method call borrows are only checked, not held.
+pub struct BorrowScope {
+    start: usize,
+    strings: usize
+}
+
+impl BorrowScope {
+    pub fn new() -> BorrowScope {
+        SCOPES.with(|scopes| scopes.set(scopes.get() + 1));
+
+        BorrowScope {
+            start: HELD.with(|held| held.borrow().len()),
+            strings: borrowed_strings()
+        }
+    }
+}
+
+impl Default for BorrowScope {
+    fn default() -> BorrowScope {
+        BorrowScope::new()
+    }
+}
+
+impl Drop for BorrowScope {
+    fn drop(&mut self) {
+        release_strings(self.strings);
+
+        let released = HELD.with(|held| held.borrow_mut().split_off(self.start));
+
+        for flag in released.into_iter().rev() {
+            let flag = unsafe { &*flag };
+
+            // Nothing else can change a mutable borrow held by this scope
+            if flag.load(Relaxed) == MUTABLY_BORROWED {
+                flag.store(UNBORROWED, Release);
+            } else {
+                flag.fetch_sub(1, Release);
+            }
+        }
+
+        SCOPES.with(|scopes| scopes.set(scopes.get() - 1));
+    }
+}
+
+// Stops releasing `flag` with the current scope, because its struct is about
+// to be freed.
+fn unhold(flag: &BorrowFlag) {
+    let flag = flag as *const BorrowFlag;
+
+    HELD.with(|held| {
+        let mut held = held.borrow_mut();
+
+        if let Some(index) = held.iter().rposition(|&held| held == flag) {
+            held.remove(index);
+        }
+    });
+}
+
+// Records a borrow to release with the current scope.
+fn hold(flag: &BorrowFlag) {
+    HELD.with(|held| held.borrow_mut().push(flag));
+}
+
+/// Whether a Helix method call is running on this thread.
+pub fn in_scope() -> bool {
+    SCOPES.with(|scopes| scopes.get()) > 0
+}
+
+fn borrowed_error(object: VALUE, count: isize) -> Error {
+    if count == MUTABLY_BORROWED {
+        borrow_error(object, "it is already mutably borrowed")
+    } else {
+        borrow_error(object, "it is already borrowed")
+    }
+}
+
+fn borrow_error(object: VALUE, reason: &str) -> Error {
+    let class = unsafe { CStr::from_ptr(sys::rb_obj_classname(object)) }.to_string_lossy();
+    let error_class = unsafe { BORROW_ERROR_CLASS }.expect("Helix::BorrowError is only defined once the extension is loaded");
+
+    format!("Cannot borrow {}, {}", class, reason).to_error().with_class(error_class)
+}
@@ -1,24 +1,37 @@
-use { Class };
+use { Class, ObjectClass };
 use sys::{self, c_string, c_func};
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    Private,
+    Protected
+}
+
 pub struct MethodSpecification {
     name: c_string,
     function: c_func,
     arity: isize,
+    visibility: Visibility,
 }
 
 pub enum MethodDefinition {
     Class(MethodSpecification),
-    Instance(MethodSpecification)
+    Instance(MethodSpecification),
+    ModuleFunction(MethodSpecification)
 }
 
 impl MethodDefinition {
-    pub fn class(name: c_string, function: c_func, arity: isize) -> MethodDefinition {
-        MethodDefinition::Class(MethodSpecification { name: name, function: function, arity: arity })
+    pub fn class(name: c_string, function: c_func, arity: isize, visibility: Visibility) -> MethodDefinition {
+        MethodDefinition::Class(MethodSpecification { name, function, arity, visibility })
+    }
+
+    pub fn instance(name: c_string, function: c_func, arity: isize, visibility: Visibility) -> MethodDefinition {
+        MethodDefinition::Instance(MethodSpecification { name, function, arity, visibility })
     }
 
-    pub fn instance(name: c_string, function: c_func, arity: isize) -> MethodDefinition {
-        MethodDefinition::Instance(MethodSpecification { name: name, function: function, arity: arity })
+    pub fn module_function(name: c_string, function: c_func, arity: isize) -> MethodDefinition {
+        MethodDefinition::ModuleFunction(MethodSpecification { name, function, arity, visibility: Visibility::Public })
     }
 }
 
@@ -28,39 +41,57 @@ pub struct ClassDefinition {
 
 impl ClassDefinition {
     pub fn new(name: c_string) -> ClassDefinition {
-        let raw_class = unsafe { sys::rb_define_class(name, sys::rb_cObject) };
+        ClassDefinition::new_under(ObjectClass(), name, ObjectClass())
+    }
+
+    pub fn new_under(namespace: Class, name: c_string, superclass: Class) -> ClassDefinition {
+        let raw_class = unsafe { sys::rb_define_class_under(namespace.0, name, superclass.0) };
         ClassDefinition { class: Class(raw_class) }
     }
 
     pub fn wrapped(name: c_string, alloc_func: extern "C" fn(klass: sys::VALUE) -> sys::VALUE) -> ClassDefinition {
-        let raw_class = unsafe { sys::rb_define_class(name, sys::rb_cObject) };
-        unsafe { sys::rb_define_alloc_func(raw_class, alloc_func) };
-        ClassDefinition { class: Class(raw_class) }
+        ClassDefinition::wrapped_under(ObjectClass(), name, ObjectClass(), alloc_func)
+    }
+
+    pub fn wrapped_under(namespace: Class, name: c_string, superclass: Class, alloc_func: extern "C" fn(klass: sys::VALUE) -> sys::VALUE) -> ClassDefinition {
+        let def = ClassDefinition::new_under(namespace, name, superclass);
+        unsafe { sys::rb_define_alloc_func(def.class.0, alloc_func) };
+        def
     }
 
     pub fn reopen(name: c_string) -> ClassDefinition {
+        ClassDefinition::reopen_under(ObjectClass(), name)
+    }
+
+    pub fn reopen_under(namespace: Class, name: c_string) -> ClassDefinition {
         let raw_class = unsafe {
             let class_id = sys::rb_intern(name);
-            sys::rb_const_get(sys::rb_cObject, class_id)
+            sys::rb_const_get(namespace.0, class_id)
         };
         ClassDefinition { class: Class(raw_class) }
     }
 
+    pub fn module(name: c_string) -> ClassDefinition {
+        ClassDefinition::module_under(ObjectClass(), name)
+    }
+
+    pub fn module_under(namespace: Class, name: c_string) -> ClassDefinition {
+        let raw_module = unsafe { sys::rb_define_module_under(namespace.0, name) };
+        ClassDefinition { class: Class(raw_module) }
+    }
+
     pub fn define_method(&self, def: MethodDefinition) {
         match def {
             MethodDefinition::Instance(def) => {
-                unsafe {
-                    sys::rb_define_method(
-                        self.class.0,
-                        def.name,
-                        def.function,
-                        def.arity
-                    );
-                };
+                define_method_with_visibility(self.class.0, def);
             },
             MethodDefinition::Class(def) => {
+                let singleton_class = unsafe { sys::rb_singleton_class(self.class.0) };
+                define_method_with_visibility(singleton_class, def);
+            },
+            MethodDefinition::ModuleFunction(def) => {
                 unsafe {
-                    sys::rb_define_singleton_method(
+                    sys::rb_define_module_function(
                         self.class.0,
                         def.name,
                         def.function,
@@ -77,3 +108,13 @@ impl ClassDefinition {
         }
     }
 }
+
+fn define_method_with_visibility(class: sys::VALUE, def: MethodSpecification) {
+    unsafe {
+        match def.visibility {
+            Visibility::Public => sys::rb_define_method(class, def.name, def.function, def.arity),
+            Visibility::Private => sys::rb_define_private_method(class, def.name, def.function, def.arity),
+            Visibility::Protected => sys::rb_define_protected_method(class, def.name, def.function, def.arity)
+        }
+    }
+}
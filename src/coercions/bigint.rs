@@ -0,0 +1,77 @@
+use num_bigint::{BigInt, BigUint, Sign};
+use sys::{self, VALUE, T_FIXNUM, T_BIGNUM};
+use super::super::{inspect, Class};
+use super::{FromRuby, CheckResult, ToRuby, ToRubyResult};
+
+// Integers are copied byte by byte with `rb_integer_pack` and
+// `rb_integer_unpack`, least significant byte first.
+const FLAGS: ::libc::c_int = sys::INTEGER_PACK_LSWORD_FIRST | sys::INTEGER_PACK_NATIVE;
+
+/// Converts a Ruby Integer to its sign and magnitude.
+fn unpack(value: VALUE) -> CheckResult<(Sign, BigUint)> {
+    if !unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
+        type_error!(value, "an Integer");
+    }
+
+    let size = unsafe { sys::rb_absint_size(value, ::std::ptr::null_mut()) };
+    let mut bytes = vec![0u8; size];
+    let sign = unsafe { sys::rb_integer_pack(value, bytes.as_mut_ptr() as *mut sys::void, size, 1, 0, FLAGS) };
+
+    let sign = match sign {
+        -1 => Sign::Minus,
+        0 => Sign::NoSign,
+        _ => Sign::Plus
+    };
+
+    Ok((sign, BigUint::from_bytes_le(&bytes)))
+}
+
+fn pack(sign: Sign, magnitude: &BigUint) -> VALUE {
+    let bytes = magnitude.to_bytes_le();
+    let flags = if sign == Sign::Minus { FLAGS | sys::INTEGER_PACK_NEGATIVE } else { FLAGS };
+
+    unsafe { sys::rb_integer_unpack(bytes.as_ptr() as *const sys::void, bytes.len(), 1, 0, flags) }
+}
+
+impl FromRuby for BigInt {
+    type Checked = BigInt;
+
+    fn from_ruby(value: VALUE) -> CheckResult<BigInt> {
+        let (sign, magnitude) = unpack(value)?;
+        Ok(BigInt::from_biguint(sign, magnitude))
+    }
+
+    fn from_checked(checked: BigInt) -> BigInt {
+        checked
+    }
+}
+
+impl ToRuby for BigInt {
+    fn to_ruby(self) -> ToRubyResult {
+        Ok(pack(self.sign(), self.magnitude()))
+    }
+}
+
+impl FromRuby for BigUint {
+    type Checked = BigUint;
+
+    fn from_ruby(value: VALUE) -> CheckResult<BigUint> {
+        match unpack(value)? {
+            (Sign::Minus, _) => {
+                let message = format!("{} is out of range for an unsigned integer", inspect(value));
+                raise!(unsafe { Class::from_value(sys::rb_eRangeError) }, message);
+            },
+            (_, magnitude) => Ok(magnitude)
+        }
+    }
+
+    fn from_checked(checked: BigUint) -> BigUint {
+        checked
+    }
+}
+
+impl ToRuby for BigUint {
+    fn to_ruby(self) -> ToRubyResult {
+        Ok(pack(Sign::Plus, &self))
+    }
+}
@@ -0,0 +1,32 @@
+use libc;
+use std;
+use sys::{self, VALUE};
+use super::super::Binary;
+use super::{FromRuby, CheckResult, CheckedValue, ToRuby, ToRubyResult};
+
+impl FromRuby for Binary {
+    type Checked = CheckedValue<Binary>;
+
+    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<Binary>> {
+        if unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+            unsafe { Ok(CheckedValue::new(value)) }
+        } else {
+            type_error!(value, "a String")
+        }
+    }
+
+    fn from_checked(checked: CheckedValue<Binary>) -> Binary {
+        let value = checked.to_value();
+        let size = unsafe { sys::RSTRING_LEN(value) };
+        let ptr = unsafe { sys::RSTRING_PTR(value) };
+        Binary::from(unsafe { std::slice::from_raw_parts(ptr as *const u8, size as usize) })
+    }
+}
+
+impl ToRuby for Binary {
+    fn to_ruby(self) -> ToRubyResult {
+        let ptr = self.as_ptr();
+        let len = self.len();
+        Ok(unsafe { sys::rb_str_new(ptr as *const libc::c_char, len as libc::c_long) })
+    }
+}
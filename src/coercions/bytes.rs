@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use libc;
+use std;
+use sys::{self, VALUE};
+use super::{FromRuby, CheckResult, CheckedValue, ToRuby, ToRubyResult};
+
+// Like `Binary`, any String converts without checking its encoding, and
+// `Bytes` converts back to an ASCII-8BIT String.
+impl FromRuby for Bytes {
+    type Checked = CheckedValue<Bytes>;
+
+    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<Bytes>> {
+        if unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+            unsafe { Ok(CheckedValue::new(value)) }
+        } else {
+            type_error!(value, "a String")
+        }
+    }
+
+    fn from_checked(checked: CheckedValue<Bytes>) -> Bytes {
+        let value = checked.to_value();
+        let size = unsafe { sys::RSTRING_LEN(value) };
+        let ptr = unsafe { sys::RSTRING_PTR(value) };
+        Bytes::copy_from_slice(unsafe { std::slice::from_raw_parts(ptr as *const u8, size as usize) })
+    }
+}
+
+impl ToRuby for Bytes {
+    fn to_ruby(self) -> ToRubyResult {
+        let ptr = self.as_ptr();
+        let len = self.len();
+        Ok(unsafe { sys::rb_str_new(ptr as *const libc::c_char, len as libc::c_long) })
+    }
+}
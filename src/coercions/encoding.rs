@@ -0,0 +1,126 @@
+use libc;
+use std;
+use sys::{self, VALUE};
+use super::super::{Class, EncodedString, LossyString, TranscodedString, protect};
+use super::{FromRuby, CheckResult, CheckedValue, ToRuby, ToRubyResult};
+
+fn is_utf8(value: VALUE) -> bool {
+    unsafe { sys::rb_enc_get_index(value) == sys::rb_utf8_encindex() }
+}
+
+fn bytes<'a>(value: VALUE) -> &'a [u8] {
+    let size = unsafe { sys::RSTRING_LEN(value) };
+    let ptr = unsafe { sys::RSTRING_PTR(value) };
+    unsafe { std::slice::from_raw_parts(ptr as *const u8, size as usize) }
+}
+
+fn utf8_str_new(string: &str) -> VALUE {
+    unsafe { sys::rb_utf8_str_new(string.as_ptr() as *const libc::c_char, string.len() as libc::c_long) }
+}
+
+fn copy_utf8(value: VALUE) -> String {
+    unsafe { std::str::from_utf8_unchecked(bytes(value)) }.to_string()
+}
+
+// The transcoded strings are new objects that nothing else refers to, so they
+// are copied right away, like `BigInt`, instead of being kept in the checked
+// value until `from_checked`.
+impl FromRuby for TranscodedString {
+    type Checked = TranscodedString;
+
+    fn from_ruby(value: VALUE) -> CheckResult<TranscodedString> {
+        if !unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+            type_error!(value, "a String");
+        }
+
+        if unsafe { sys::rb_str_ascii_only_p(value) } {
+            return Ok(TranscodedString(copy_utf8(value)));
+        }
+
+        // Returns the string unchanged if it can't be converted
+        let transcoded = protect(|| unsafe { sys::rb_str_conv_enc(value, std::ptr::null_mut(), sys::rb_utf8_encoding()) })?;
+
+        if is_utf8(transcoded) && unsafe { sys::rb_str_valid_encoding_p(transcoded) } {
+            Ok(TranscodedString(copy_utf8(transcoded)))
+        } else {
+            type_error!(value, "a String that can be transcoded to UTF-8")
+        }
+    }
+
+    fn from_checked(checked: TranscodedString) -> TranscodedString {
+        checked
+    }
+}
+
+impl ToRuby for TranscodedString {
+    fn to_ruby(self) -> ToRubyResult {
+        Ok(utf8_str_new(&self))
+    }
+}
+
+impl FromRuby for LossyString {
+    type Checked = LossyString;
+
+    fn from_ruby(value: VALUE) -> CheckResult<LossyString> {
+        if !unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+            type_error!(value, "a String");
+        }
+
+        // Invalid UTF-8 is replaced when copying
+        if is_utf8(value) || unsafe { sys::rb_str_ascii_only_p(value) } {
+            return Ok(LossyString(String::from_utf8_lossy(bytes(value)).into_owned()));
+        }
+
+        let flags = sys::ECONV_INVALID_REPLACE | sys::ECONV_UNDEF_REPLACE;
+
+        let transcoded = protect(|| unsafe {
+            sys::rb_str_encode(value, sys::rb_enc_from_encoding(sys::rb_utf8_encoding()), flags, sys::Qnil)
+        })?;
+
+        Ok(LossyString(String::from_utf8_lossy(bytes(transcoded)).into_owned()))
+    }
+
+    fn from_checked(checked: LossyString) -> LossyString {
+        checked
+    }
+}
+
+impl ToRuby for LossyString {
+    fn to_ruby(self) -> ToRubyResult {
+        Ok(utf8_str_new(&self))
+    }
+}
+
+impl FromRuby for EncodedString {
+    type Checked = CheckedValue<EncodedString>;
+
+    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<EncodedString>> {
+        if unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+            unsafe { Ok(CheckedValue::new(value)) }
+        } else {
+            type_error!(value, "a String")
+        }
+    }
+
+    fn from_checked(checked: CheckedValue<EncodedString>) -> EncodedString {
+        let value = checked.to_value();
+        EncodedString::new(bytes(value).to_vec(), unsafe { sys::rb_enc_get_index(value) })
+    }
+}
+
+impl ToRuby for EncodedString {
+    fn to_ruby(self) -> ToRubyResult {
+        let bytes = self.bytes();
+        let index = self.encoding_index();
+
+        // `rb_enc_associate_index` raises for unknown indexes
+        if unsafe { sys::rb_enc_from_index(index) }.is_null() {
+            raise!(unsafe { Class::from_value(sys::rb_eArgError) }, format!("invalid encoding index {}", index));
+        }
+
+        unsafe {
+            let string = sys::rb_str_new(bytes.as_ptr() as *const libc::c_char, bytes.len() as libc::c_long);
+            Ok(sys::rb_enc_associate_index(string, index))
+        }
+    }
+}
@@ -1,139 +1,109 @@
+use std::convert::TryFrom;
 use std::mem::size_of;
 use sys::{self, VALUE, T_FIXNUM, T_BIGNUM};
-use super::{FromRuby, CheckResult, CheckedValue, ToRuby, ToRubyResult};
-
-impl FromRuby for usize {
-    type Checked = CheckedValue<usize>;
-
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<usize>> {
-        if unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
-            Ok(unsafe { CheckedValue::new(value) })
-        } else if size_of::<usize>() == size_of::<u32>() {
-            type_error!(value, "a 32-bit unsigned integer")
-        } else {
-            type_error!(value, "a 64-bit unsigned integer")
-        }
-    }
-
-    fn from_checked(checked: CheckedValue<usize>) -> usize {
-        unsafe { sys::NUM2USIZE(checked.to_value()) }
-    }
-}
-
-impl ToRuby for usize {
-    fn to_ruby(self) -> ToRubyResult {
-        Ok(unsafe { sys::USIZE2NUM(self) })
-    }
-}
-
-impl FromRuby for isize {
-    type Checked = CheckedValue<isize>;
+use super::super::{inspect, Class};
+use super::{FromRuby, CheckResult, ToRuby, ToRubyResult};
 
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<isize>> {
-        if unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
-            Ok(unsafe { CheckedValue::new(value) })
-        } else if size_of::<isize>() == size_of::<i32>() {
-            type_error!(value, "a 32-bit signed integer")
-        } else {
-            type_error!(value, "a 64-bit signed integer")
-        }
-    }
+// Integers are converted and range checked in `from_ruby`. The `NUM2*`
+// macros raise a `RangeError` with a longjmp, skipping Rust destructors.
 
-    fn from_checked(checked: CheckedValue<isize>) -> isize {
-        unsafe { sys::NUM2ISIZE(checked.to_value()) }
-    }
+fn is_integer(value: VALUE) -> bool {
+    unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) }
 }
 
-impl ToRuby for isize {
-    fn to_ruby(self) -> ToRubyResult {
-        Ok(unsafe { sys::ISIZE2NUM(self) })
+/// Converts a Ruby Integer to a sign and magnitude, or `None` if the
+/// magnitude doesn't fit in 128 bits. `rb_integer_pack` doesn't raise.
+fn to_magnitude(value: VALUE) -> Option<(bool, u128)> {
+    let mut magnitude: u128 = 0;
+
+    let sign = unsafe {
+        sys::rb_integer_pack(
+            value,
+            &mut magnitude as *mut u128 as *mut sys::void,
+            1,
+            size_of::<u128>(),
+            0,
+            sys::INTEGER_PACK_LSWORD_FIRST | sys::INTEGER_PACK_NATIVE
+        )
+    };
+
+    match sign {
+        -1 => Some((true, magnitude)),
+        0 | 1 => Some((false, magnitude)),
+        _ => None
     }
 }
 
-impl FromRuby for u64 {
-    type Checked = CheckedValue<u64>;
-
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<u64>> {
-        if unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
-            Ok(unsafe { CheckedValue::new(value) })
-        } else {
-            type_error!(value, "a 64-bit unsigned integer")
-        }
-    }
-
-    fn from_checked(checked: CheckedValue<u64>) -> u64 {
-        unsafe { sys::NUM2U64(checked.to_value()) }
+fn to_u128(value: VALUE) -> Option<u128> {
+    match to_magnitude(value) {
+        Some((false, magnitude)) => Some(magnitude),
+        _ => None
     }
 }
 
-impl ToRuby for u64 {
-    fn to_ruby(self) -> ToRubyResult {
-        Ok(unsafe { sys::U642NUM(self) })
+fn to_i128(value: VALUE) -> Option<i128> {
+    match to_magnitude(value) {
+        Some((false, magnitude)) => i128::try_from(magnitude).ok(),
+        Some((true, magnitude)) if magnitude <= 1 << 127 => Some((magnitude as i128).wrapping_neg()),
+        _ => None
     }
 }
 
-impl FromRuby for i64 {
-    type Checked = CheckedValue<i64>;
-
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<i64>> {
-        if unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
-            Ok(unsafe { CheckedValue::new(value) })
-        } else {
-            type_error!(value, "a 64-bit signed integer")
+macro_rules! impl_integer_coercions {
+    ($ty:ident, $via:ident, $to_ruby:expr, $description:expr) => {
+        impl FromRuby for $ty {
+            type Checked = $ty;
+
+            fn from_ruby(value: VALUE) -> CheckResult<$ty> {
+                if !is_integer(value) {
+                    type_error!(value, $description);
+                }
+
+                match $via(value).and_then(|integer| $ty::try_from(integer).ok()) {
+                    Some(integer) => Ok(integer),
+                    None => {
+                        let message = format!("{} is out of range for {}", inspect(value), $description);
+                        raise!(unsafe { Class::from_value(sys::rb_eRangeError) }, message);
+                    }
+                }
+            }
+
+            fn from_checked(checked: $ty) -> $ty {
+                checked
+            }
         }
-    }
 
-    fn from_checked(checked: CheckedValue<i64>) -> i64 {
-        unsafe { sys::NUM2I64(checked.to_value()) }
-    }
-}
-
-impl ToRuby for i64 {
-    fn to_ruby(self) -> ToRubyResult {
-        Ok(unsafe { sys::I642NUM(self) })
-    }
-}
-
-impl FromRuby for u32 {
-    type Checked = CheckedValue<u32>;
-
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<u32>> {
-        if unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
-            Ok(unsafe { CheckedValue::new(value) })
-        } else {
-            type_error!(value, "a 32-bit unsigned integer")
+        impl ToRuby for $ty {
+            fn to_ruby(self) -> ToRubyResult {
+                Ok(unsafe { $to_ruby(self) })
+            }
         }
     }
-
-    fn from_checked(checked: CheckedValue<u32>) -> u32 {
-        unsafe { sys::NUM2U32(checked.to_value()) }
-    }
 }
 
-impl ToRuby for u32 {
-    fn to_ruby(self) -> ToRubyResult {
-        Ok(unsafe { sys::U322NUM(self) })
-    }
+impl_integer_coercions!(u8, to_u128, |num| sys::U322NUM(num as u32), "an 8-bit unsigned integer");
+impl_integer_coercions!(i8, to_i128, |num| sys::I322NUM(num as i32), "an 8-bit signed integer");
+impl_integer_coercions!(u16, to_u128, |num| sys::U322NUM(num as u32), "a 16-bit unsigned integer");
+impl_integer_coercions!(i16, to_i128, |num| sys::I322NUM(num as i32), "a 16-bit signed integer");
+impl_integer_coercions!(u32, to_u128, sys::U322NUM, "a 32-bit unsigned integer");
+impl_integer_coercions!(i32, to_i128, sys::I322NUM, "a 32-bit signed integer");
+impl_integer_coercions!(u64, to_u128, sys::U642NUM, "a 64-bit unsigned integer");
+impl_integer_coercions!(i64, to_i128, sys::I642NUM, "a 64-bit signed integer");
+impl_integer_coercions!(u128, to_u128, u128_to_ruby, "a 128-bit unsigned integer");
+impl_integer_coercions!(i128, to_i128, i128_to_ruby, "a 128-bit signed integer");
+
+impl_integer_coercions!(usize, to_u128, sys::USIZE2NUM,
+    if size_of::<usize>() == size_of::<u32>() { "a 32-bit unsigned integer" } else { "a 64-bit unsigned integer" });
+
+impl_integer_coercions!(isize, to_i128, sys::ISIZE2NUM,
+    if size_of::<isize>() == size_of::<i32>() { "a 32-bit signed integer" } else { "a 64-bit signed integer" });
+
+unsafe fn u128_to_ruby(num: u128) -> VALUE {
+    let flags = sys::INTEGER_PACK_LSWORD_FIRST | sys::INTEGER_PACK_NATIVE;
+    sys::rb_integer_unpack(&num as *const u128 as *const sys::void, 1, size_of::<u128>(), 0, flags)
 }
 
-impl FromRuby for i32 {
-    type Checked = CheckedValue<i32>;
-
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<i32>> {
-        if unsafe { sys::RB_TYPE_P(value, T_FIXNUM) || sys::RB_TYPE_P(value, T_BIGNUM) } {
-            Ok(unsafe { CheckedValue::new(value) })
-        } else {
-            type_error!(value, "a 32-bit signed integer")
-        }
-    }
-
-    fn from_checked(checked: CheckedValue<i32>) -> i32 {
-        unsafe { sys::NUM2I32(checked.to_value()) }
-    }
-}
-
-impl ToRuby for i32 {
-    fn to_ruby(self) -> ToRubyResult {
-        Ok(unsafe { sys::I322NUM(self) })
-    }
+unsafe fn i128_to_ruby(num: i128) -> VALUE {
+    let flags = sys::INTEGER_PACK_LSWORD_FIRST | sys::INTEGER_PACK_NATIVE | sys::INTEGER_PACK_2COMP;
+    sys::rb_integer_unpack(&num as *const i128 as *const sys::void, 1, size_of::<i128>(), 0, flags)
 }
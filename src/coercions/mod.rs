@@ -5,12 +5,18 @@ mod integers;
 mod float;
 mod symbol;
 mod string;
+mod binary;
+mod encoding;
 mod tuples;
 mod option;
 mod result;
 mod slice;
 mod vec;
 mod hash;
+#[cfg(feature = "bigint")]
+mod bigint;
+#[cfg(feature = "bytes")]
+mod bytes;
 
 use sys::{VALUE};
 use super::{Error, ToError};
@@ -27,6 +33,36 @@ pub trait FromRuby : Sized {
     }
 }
 
+/// Converts the arguments of a Helix method.
+///
+/// This is `FromRuby`, plus `&str` and `&[u8]` (and `Vec`s and `Option`s of
+/// them), which borrow the string's buffer for as long as the method call.
+/// Only the generated method glue uses it.
+///
+/// # Safety
+///
+/// `from_checked_arg` must be called inside a `BorrowScope`, and its result
+/// must not outlive the scope.
+#[doc(hidden)]
+pub trait FromRubyArg : Sized {
+    type Checked;
+
+    fn check_arg(value: VALUE) -> CheckResult<Self::Checked>;
+    unsafe fn from_checked_arg(checked: Self::Checked) -> Self;
+}
+
+impl<T: FromRuby> FromRubyArg for T {
+    type Checked = T::Checked;
+
+    fn check_arg(value: VALUE) -> CheckResult<T::Checked> {
+        T::from_ruby(value)
+    }
+
+    unsafe fn from_checked_arg(checked: T::Checked) -> T {
+        T::from_checked(checked)
+    }
+}
+
 pub type CheckResult<T> = Result<T, Error>;
 
 pub struct CheckedValue<T> {
@@ -49,3 +85,14 @@ pub type ToRubyResult = Result<VALUE, Error>;
 pub trait ToRuby {
     fn to_ruby(self) -> ToRubyResult;
 }
+
+/// Converts a tuple into a list of Ruby arguments, e.g. when calling a block.
+///
+/// The arguments are returned in a fixed-size array rather than on the heap,
+/// so that they stay on the stack (where Ruby's GC can see them) while the
+/// remaining arguments are converted and until the call returns.
+pub trait ToRubyArgs {
+    type Args: AsRef<[VALUE]>;
+
+    fn to_ruby_args(self) -> Result<Self::Args, Error>;
+}
@@ -2,29 +2,35 @@ use libc;
 use std;
 use sys;
 use sys::{VALUE};
-use super::{FromRuby, CheckResult, CheckedValue, ToRuby, ToRubyResult};
+use super::super::string_borrow::borrow_string;
+use super::{FromRuby, FromRubyArg, CheckResult, CheckedValue, ToRuby, ToRubyResult};
+use super::vec::check_elements;
 
-impl FromRuby for String {
-    type Checked = CheckedValue<String>;
-
-    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<String>> {
-        if unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
-            if unsafe { sys::rb_enc_get_index(value) == sys::rb_utf8_encindex() } {
-                if unsafe { sys::rb_str_valid_encoding_p(value) } {
-                    unsafe { Ok(CheckedValue::new(value)) }
-                } else {
-                    type_error!(value, "a valid UTF-8 String")
-                }
+fn check_utf8<T>(value: VALUE) -> CheckResult<CheckedValue<T>> {
+    if unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+        if unsafe { sys::rb_enc_get_index(value) == sys::rb_utf8_encindex() } {
+            if unsafe { sys::rb_str_valid_encoding_p(value) } {
+                unsafe { Ok(CheckedValue::new(value)) }
             } else {
-                if unsafe { sys::rb_str_ascii_only_p(value) } {
-                    unsafe { Ok(CheckedValue::new(value)) }
-                } else {
-                    type_error!(value, "an UTF-8 String")
-                }
+                type_error!(value, "a valid UTF-8 String")
             }
         } else {
-            type_error!(value, "a String")
+            if unsafe { sys::rb_str_ascii_only_p(value) } {
+                unsafe { Ok(CheckedValue::new(value)) }
+            } else {
+                type_error!(value, "an UTF-8 String")
+            }
         }
+    } else {
+        type_error!(value, "a String")
+    }
+}
+
+impl FromRuby for String {
+    type Checked = CheckedValue<String>;
+
+    fn from_ruby(value: VALUE) -> CheckResult<CheckedValue<String>> {
+        check_utf8(value)
     }
 
     fn from_checked(checked: CheckedValue<String>) -> String {
@@ -36,6 +42,73 @@ impl FromRuby for String {
     }
 }
 
+// Borrows the string's buffer instead of copying it. The string is kept alive
+// and locked against modification until the Helix method call returns, which
+// is why these are only available as method arguments.
+impl<'a> FromRubyArg for &'a str {
+    type Checked = CheckedValue<&'a str>;
+
+    fn check_arg(value: VALUE) -> CheckResult<CheckedValue<&'a str>> {
+        check_utf8(value)
+    }
+
+    unsafe fn from_checked_arg(checked: CheckedValue<&'a str>) -> &'a str {
+        std::str::from_utf8_unchecked(borrow_string(checked.to_value()))
+    }
+}
+
+// The bytes of a String in any encoding, borrowed like `&str`.
+impl<'a> FromRubyArg for &'a [u8] {
+    type Checked = CheckedValue<&'a [u8]>;
+
+    fn check_arg(value: VALUE) -> CheckResult<CheckedValue<&'a [u8]>> {
+        if unsafe { sys::RB_TYPE_P(value, sys::T_STRING) } {
+            unsafe { Ok(CheckedValue::new(value)) }
+        } else {
+            type_error!(value, "a String")
+        }
+    }
+
+    unsafe fn from_checked_arg(checked: CheckedValue<&'a [u8]>) -> &'a [u8] {
+        borrow_string(checked.to_value())
+    }
+}
+
+macro_rules! impl_borrowed_containers {
+    ($ty:ty) => {
+        impl<'a> FromRubyArg for Option<$ty> {
+            type Checked = Option<<$ty as FromRubyArg>::Checked>;
+
+            fn check_arg(value: VALUE) -> CheckResult<Self::Checked> {
+                if unsafe { value == sys::Qnil } {
+                    Ok(None)
+                } else {
+                    <$ty>::check_arg(value).map(Some)
+                }
+            }
+
+            unsafe fn from_checked_arg(checked: Self::Checked) -> Self {
+                checked.map(|checked| <$ty>::from_checked_arg(checked))
+            }
+        }
+
+        impl<'a> FromRubyArg for Vec<$ty> {
+            type Checked = Vec<<$ty as FromRubyArg>::Checked>;
+
+            fn check_arg(value: VALUE) -> CheckResult<Self::Checked> {
+                check_elements(value, <$ty>::check_arg)
+            }
+
+            unsafe fn from_checked_arg(checked: Self::Checked) -> Self {
+                checked.into_iter().map(|checked| <$ty>::from_checked_arg(checked)).collect()
+            }
+        }
+    }
+}
+
+impl_borrowed_containers!(&'a str);
+impl_borrowed_containers!(&'a [u8]);
+
 impl ToRuby for String {
     fn to_ruby(self) -> ToRubyResult {
         let ptr = self.as_ptr();
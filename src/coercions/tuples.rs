@@ -1,6 +1,7 @@
 use sys::{self, VALUE};
 
-use super::{CheckResult, FromRuby, ToRuby, ToRubyResult};
+use super::{CheckResult, FromRuby, ToRuby, ToRubyArgs, ToRubyResult};
+use super::super::Error;
 use super::super::{inspect};
 
 #[doc(hidden)]
@@ -8,6 +9,7 @@ macro_rules! impl_tuple_coercions {
     ($($name:ident),*) => {
         impl_tuple_from_ruby!(count_items!($($name),*), $($name),*);
         impl_tuple_to_ruby!(count_items!($($name),*), $($name),*);
+        impl_tuple_to_ruby_args!(count_items!($($name),*), $($name),*);
     };
 
     ($($any:tt)*) => {
@@ -97,6 +99,34 @@ macro_rules! impl_tuple_to_ruby {
     };
 }
 
+#[doc(hidden)]
+macro_rules! impl_tuple_to_ruby_args {
+    ($count:expr, $($name:ident),*) => {
+        impl<$($name: ToRuby,)*> ToRubyArgs for ($($name,)*) {
+            type Args = [VALUE; $count];
+
+            fn to_ruby_args(self) -> Result<Self::Args, Error> {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+
+                Ok([$($name.to_ruby()?,)*])
+            }
+        }
+    };
+
+    ($($any:tt)*) => {
+        compile_error!(stringify!("impl_tuple_to_ruby_args" $($any)*));
+    };
+}
+
+impl ToRubyArgs for () {
+    type Args = [VALUE; 0];
+
+    fn to_ruby_args(self) -> Result<Self::Args, Error> {
+        Ok([])
+    }
+}
+
 #[doc(hidden)]
 macro_rules! count_items {
     () => { 0 };
@@ -0,0 +1,40 @@
+use std::marker::PhantomData;
+
+/// Clones a wrapped struct for `initialize_copy`, if it implements `Clone`.
+///
+/// The generated glue calls `(&CopyProbe::<T>::new()).copy(value)`. Method
+/// resolution picks `CopyClone` when `T: Clone`, and only falls back to the
+/// auto-referenced `CopyUnsupported` when it doesn't.
+pub struct CopyProbe<T>(PhantomData<T>);
+
+impl<T> CopyProbe<T> {
+    pub fn new() -> CopyProbe<T> {
+        CopyProbe(PhantomData)
+    }
+}
+
+impl<T> Default for CopyProbe<T> {
+    fn default() -> CopyProbe<T> {
+        CopyProbe::new()
+    }
+}
+
+pub trait CopyClone<T> {
+    fn copy(&self, value: &T) -> Option<T>;
+}
+
+impl<T: Clone> CopyClone<T> for CopyProbe<T> {
+    fn copy(&self, value: &T) -> Option<T> {
+        Some(value.clone())
+    }
+}
+
+pub trait CopyUnsupported<T> {
+    fn copy(&self, value: &T) -> Option<T>;
+}
+
+impl<T> CopyUnsupported<T> for &CopyProbe<T> {
+    fn copy(&self, _value: &T) -> Option<T> {
+        None
+    }
+}
@@ -0,0 +1,94 @@
+use libc::c_int;
+use std::ops::Deref;
+use std::str;
+use sys;
+
+/// A UTF-8 `String` converted from a Ruby String in any encoding.
+///
+/// Strings in other encodings, such as Shift_JIS or ISO-8859-1, are
+/// transcoded to UTF-8 with `rb_str_conv_enc`. Strings that can't be
+/// transcoded raise a `TypeError`, like `String` does.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TranscodedString(pub String);
+
+/// Like `TranscodedString`, but invalid or unconvertible bytes are replaced
+/// with U+FFFD instead of raising.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LossyString(pub String);
+
+macro_rules! impl_utf8_string {
+    ($name:ident) => {
+        impl $name {
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = String;
+
+            fn deref(&self) -> &String {
+                &self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(string: String) -> $name {
+                $name(string)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(string: $name) -> String {
+                string.0
+            }
+        }
+    }
+}
+
+impl_utf8_string!(TranscodedString);
+impl_utf8_string!(LossyString);
+
+/// The bytes of a Ruby String along with its encoding, so that it converts
+/// back to Ruby unchanged. The bytes aren't validated or transcoded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EncodedString {
+    bytes: Vec<u8>,
+    encoding: c_int
+}
+
+impl EncodedString {
+    /// Wraps bytes in the encoding with the index `encoding`, as returned by
+    /// `rb_enc_get_index`. An index that isn't a loaded encoding raises an
+    /// `ArgumentError` when the string is converted to Ruby.
+    pub fn new(bytes: Vec<u8>, encoding: c_int) -> EncodedString {
+        EncodedString { bytes, encoding }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn encoding_index(&self) -> c_int {
+        self.encoding
+    }
+
+    /// The string as a `&str`, if it is valid UTF-8 in the UTF-8 encoding.
+    pub fn as_str(&self) -> Option<&str> {
+        if self.encoding == unsafe { sys::rb_utf8_encindex() } {
+            str::from_utf8(&self.bytes).ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl From<String> for EncodedString {
+    fn from(string: String) -> EncodedString {
+        EncodedString::new(string.into_bytes(), unsafe { sys::rb_utf8_encindex() })
+    }
+}
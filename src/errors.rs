@@ -1,6 +1,6 @@
-use super::{Class, ToRuby};
+use super::{Class, ToRuby, protect};
 use std::{any, fmt};
-use sys::{VALUE, SPRINTF_TO_S, c_string, rb_eRuntimeError, rb_raise};
+use sys::{self, VALUE, SPRINTF_TO_S, RubyException, c_string, rb_eRuntimeError, rb_raise, rb_jump_tag};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Error {
@@ -11,7 +11,9 @@ pub struct Error {
 #[derive(Copy, Clone, Debug)]
 enum ErrorMessage {
     Static(c_string),
-    Dynamic(VALUE)
+    Dynamic(VALUE),
+    Exception(VALUE),
+    Tag(RubyException)
 }
 
 impl Error {
@@ -23,22 +25,57 @@ impl Error {
         Error { class: unsafe { Class(rb_eRuntimeError) }, message: ErrorMessage::Dynamic(message) }
     }
 
+    /// An error wrapping an existing Ruby exception object. Raising it raises
+    /// that same object, keeping its backtrace, `cause` and any other state.
+    pub fn with_exception(exception: VALUE) -> Error {
+        Error { class: unsafe { Class(sys::rb_obj_class(exception)) }, message: ErrorMessage::Exception(exception) }
+    }
+
+    /// An error for a non-local exit (an exception, `break`, `throw`, ...)
+    /// that was stopped by `rb_protect`. Raising it resumes the exit.
+    pub fn with_tag(state: RubyException) -> Error {
+        if state == sys::RAISED_EXCEPTION {
+            let exception = unsafe { sys::rb_errinfo() };
+
+            if super::is_kind_of(exception, unsafe { Class(sys::rb_eException) }) {
+                unsafe { sys::rb_set_errinfo(sys::Qnil) };
+                return Error::with_exception(exception);
+            }
+        }
+
+        Error { class: unsafe { Class(rb_eRuntimeError) }, message: ErrorMessage::Tag(state) }
+    }
+
+    /// Converts the payload of a caught panic. Panicking with an `Error`
+    /// (see `raise_panic!`) raises it as is, any other panic raises a
+    /// `Helix::Panic`.
     pub fn from_any(any: Box<any::Any>) -> Error {
         any.downcast::<Error>()
             .map(|e| *e)
-            .or_else(|any| any.downcast::<&str>().map(|e| e.to_error()))
-            .or_else(|any| any.downcast::<String>().map(|e| e.to_error()))
-            .unwrap_or_else(|any| format!("Unknown Error (caused by `{:?}`)", any).to_error())
+            .unwrap_or_else(super::panic::to_error)
+    }
+
+    /// Changes the class of the error, which can be a `Class` or an
+    /// `exception` declared in `ruby!`. An error wrapping an existing Ruby
+    /// exception keeps raising that exception.
+    pub fn with_class<C: Into<Class>>(self, class: C) -> Error {
+        Error { class: class.into(), message: self.message }
     }
 
-    pub fn with_class(self, class: Class) -> Error {
-        Error { class, message: self.message }
+    /// The Ruby exception object this error wraps, if it came from Ruby.
+    pub fn exception(&self) -> Option<VALUE> {
+        match self.message {
+            ErrorMessage::Exception(exception) => Some(exception),
+            _ => None
+        }
     }
 
     pub unsafe fn raise(self) -> ! {
         match self.message {
             ErrorMessage::Static(c_string) => rb_raise(self.class.to_value(), c_string),
-            ErrorMessage::Dynamic(value) => rb_raise(self.class.to_value(), SPRINTF_TO_S, value)
+            ErrorMessage::Dynamic(value) => rb_raise(self.class.to_value(), SPRINTF_TO_S, value),
+            ErrorMessage::Exception(exception) => sys::rb_exc_raise(exception),
+            ErrorMessage::Tag(state) => rb_jump_tag(state)
         }
     }
 }
@@ -53,6 +90,12 @@ impl fmt::Display for Error {
             ErrorMessage::Dynamic(value) => {
                 use super::FromRuby;
                 write!(f, "{}", String::from_ruby_unwrap(value))
+            },
+            ErrorMessage::Exception(exception) => {
+                write!(f, "{}", super::inspect(exception))
+            },
+            ErrorMessage::Tag(_) => {
+                write!(f, "Non-local exit from Ruby")
             }
         }
     }
@@ -61,6 +104,17 @@ impl fmt::Display for Error {
 unsafe impl Send for Error {}
 unsafe impl Sync for Error {}
 
+/// Raises Ruby's `FrozenError` if `object` is frozen. The generated glue
+/// calls this before running a `&mut self` or `self` method.
+pub fn check_frozen(object: VALUE) -> Result<(), Error> {
+    if unsafe { sys::OBJ_FROZEN(object) } {
+        // Raised by Ruby itself, so the message matches the built-in classes
+        protect(|| unsafe { sys::rb_error_frozen_object(object) })?;
+    }
+
+    Ok(())
+}
+
 pub trait ToError {
     fn to_error(self) -> Error;
 }
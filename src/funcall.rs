@@ -0,0 +1,37 @@
+use libc::c_int;
+use sys::{self, VALUE};
+use super::{Error, FromRuby, Symbol, ToRubyArgs, protect};
+
+/// Calls methods on Ruby objects from Rust.
+///
+/// ```ignore
+/// use helix::Funcall;
+///
+/// let length: usize = value.call("length", ())?;
+/// let joined: String = value.call("join", (", ",))?;
+/// ```
+pub trait Funcall {
+    /// Calls `method` with `args` and converts its result.
+    ///
+    /// `args` is a tuple, like for `Block::call`. The method is looked up the
+    /// way `send` does, so private methods can be called too. Exceptions
+    /// raised by the method (as well as `throw`) are returned as an `Err`
+    /// instead of unwinding through Rust frames; raising that `Error` back
+    /// into Ruby resumes the original exception.
+    fn call<T: FromRuby>(&self, method: &str, args: impl ToRubyArgs) -> Result<T, Error>;
+}
+
+impl Funcall for VALUE {
+    fn call<T: FromRuby>(&self, method: &str, args: impl ToRubyArgs) -> Result<T, Error> {
+        let receiver = *self;
+        let method = Symbol::from_string(method.to_string()).to_id();
+        let args = args.to_ruby_args()?;
+        let args = args.as_ref();
+
+        let result = protect(|| unsafe {
+            sys::rb_funcallv(receiver, method, args.len() as c_int, args.as_ptr())
+        })?;
+
+        T::from_ruby(result).map(T::from_checked)
+    }
+}
@@ -0,0 +1,105 @@
+use std::cell::Cell;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use sys;
+use super::{Error, protect};
+use super::protect::CaughtPanic;
+
+thread_local! {
+    static INTERRUPTED: Cell<*const AtomicBool> = const { Cell::new(ptr::null()) };
+}
+
+/// Runs `func` without holding the global VM lock, so that other Ruby threads
+/// can run while it does.
+///
+/// `func` must not use the Ruby API. The `Send` bounds turn capturing or
+/// returning a `VALUE` into a compile-time error, but they don't catch
+/// everything: creating a `helix::Error` (e.g. with `raise!` or `to_error`)
+/// allocates Ruby objects, so `func` must not do that either.
+///
+/// If Ruby interrupts the thread while `func` is running (`Thread#raise`,
+/// `Timeout.timeout`, Ctrl-C, ...), `interrupted` starts returning true so
+/// that `func` can return early. The interrupt itself is delivered as an
+/// `Err` once the lock is reacquired, and `func`'s result is dropped.
+pub fn without_gvl<F, R>(func: F) -> Result<R, Error> where F: FnOnce() -> R + Send, R: Send {
+    // The flag lives outside of `Closure`, which `call` borrows mutably while
+    // `unblock` sets the flag from another thread
+    struct Closure<'a, F, R> {
+        func: Option<F>,
+        result: Option<R>,
+        panic: CaughtPanic,
+        interrupted: &'a AtomicBool
+    }
+
+    extern "C" fn call<F: FnOnce() -> R, R>(data: *mut sys::void) -> *mut sys::void {
+        let closure = unsafe { &mut *(data as *mut Closure<F, R>) };
+        let func = closure.func.take().expect("without_gvl callback can only be called once");
+
+        let previous = INTERRUPTED.with(|current| current.replace(closure.interrupted));
+        closure.result = closure.panic.catch(func);
+        INTERRUPTED.with(|current| current.set(previous));
+
+        ptr::null_mut()
+    }
+
+    // Called by Ruby, possibly from another thread, to ask `func` to stop
+    extern "C" fn unblock(data: *mut sys::void) {
+        let interrupted = unsafe { &*(data as *const AtomicBool) };
+        interrupted.store(true, Ordering::SeqCst);
+    }
+
+    let interrupted = AtomicBool::new(false);
+    let mut closure = Closure { func: Some(func), result: None, panic: CaughtPanic::default(), interrupted: &interrupted };
+
+    let status = {
+        let data = &mut closure as *mut Closure<F, R> as *mut sys::void;
+        let interrupted = &interrupted as *const AtomicBool as *mut sys::void;
+
+        // Ruby checks for pending interrupts around the unlocked region, which
+        // raises, so this has to be protected as well.
+        protect(|| unsafe {
+            sys::rb_thread_call_without_gvl(call::<F, R>, data, unblock, interrupted);
+            sys::Qnil
+        })
+    };
+
+    closure.panic.resume();
+
+    status?;
+
+    Ok(closure.result.expect("Ruby did not call the without_gvl callback"))
+}
+
+/// Returns true if Ruby asked the current `without_gvl` (or `#[ruby_nogvl]`)
+/// call to stop. Always false while holding the GVL.
+pub fn interrupted() -> bool {
+    INTERRUPTED.with(|current| {
+        let interrupted = current.get();
+        !interrupted.is_null() && unsafe { (*interrupted).load(Ordering::SeqCst) }
+    })
+}
+
+/// Gives Ruby a chance to handle pending interrupts (`Thread#raise`, `Thread#kill`,
+/// `Timeout.timeout`, signals such as Ctrl-C, ...) from a long-running method.
+///
+/// An interrupt that would raise is returned as an `Err`, so the caller can
+/// clean up and return it with `?`, which delivers it once it reaches Ruby.
+/// Inside `without_gvl` (where Ruby can't be called) this only reports
+/// whether the thread has been interrupted; the interrupt itself is delivered
+/// when the GVL is reacquired.
+pub fn check_interrupts() -> Result<(), Error> {
+    let unlocked = INTERRUPTED.with(|current| !current.get().is_null());
+
+    if unlocked {
+        if interrupted() {
+            Err(Error::with_c_string(cstr!("interrupted")))
+        } else {
+            Ok(())
+        }
+    } else {
+        protect(|| unsafe {
+            sys::rb_thread_check_ints();
+            sys::Qnil
+        }).map(|_| ())
+    }
+}
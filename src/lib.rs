@@ -12,6 +12,18 @@ pub extern crate libc;
 
 #[doc(hidden)]
 pub extern crate libcruby_sys as sys;
+
+#[cfg(feature = "marshal")]
+extern crate bincode;
+
+#[cfg(feature = "marshal")]
+extern crate serde;
+
+#[cfg(feature = "bigint")]
+extern crate num_bigint;
+
+#[cfg(feature = "bytes")]
+extern crate bytes;
 // pub use rb;
 
 use std::ffi::CStr;
@@ -46,13 +58,47 @@ macro_rules! type_error {
     };
 }
 
+mod arguments;
+mod binary;
+mod block;
+mod borrow;
 mod class_definition;
 mod coercions;
+mod copy;
+mod encoding;
 mod errors;
+mod funcall;
+mod gvl;
 mod macros;
+mod mark;
+#[cfg(feature = "marshal")]
+mod marshal;
+mod memsize;
+mod panic;
+mod protect;
+mod string_borrow;
 
+#[doc(hidden)]
+pub use arguments::{check_arity, Keywords};
+pub use binary::Binary;
+pub use block::Block;
+#[doc(hidden)]
+pub use borrow::{BorrowScope, Wrapped};
 pub use coercions::*;
+#[doc(hidden)]
+pub use copy::{CopyClone, CopyProbe, CopyUnsupported};
+pub use encoding::{EncodedString, LossyString, TranscodedString};
 pub use errors::*;
+pub use funcall::Funcall;
+pub use gvl::{check_interrupts, interrupted, without_gvl};
+pub use mark::Mark;
+#[cfg(feature = "marshal")]
+#[doc(hidden)]
+pub use marshal::{marshal_dump, marshal_load, marshal_nil};
+pub use memsize::MemSize;
+#[doc(hidden)]
+pub use panic::{catch_panic, check_poisoned, poison};
+pub use protect::protect;
 
 
 #[repr(C)]
@@ -83,7 +129,7 @@ impl Symbol {
     }
 }
 
-pub use class_definition::{ClassDefinition, MethodDefinition};
+pub use class_definition::{ClassDefinition, MethodDefinition, Visibility};
 
 #[repr(C)]
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
@@ -147,12 +193,47 @@ impl Class {
     pub fn define_method<T: RubyMethod>(&self, name: &CStr, method: T) {
         method.install(self.0, name);
     }
+
+    /// Resolves a constant path such as `"Foo::Bar"` relative to this class
+    /// or module, falling back to top-level constants. A leading `::` always
+    /// starts the lookup from `Object`.
+    pub fn lookup(&self, path: &str) -> Class {
+        let mut namespace = if path.trim().starts_with("::") { ObjectClass() } else { *self };
+
+        for segment in path.split("::").map(str::trim).filter(|s| !s.is_empty()) {
+            let id = Symbol::from_string(segment.to_string()).to_id();
+            namespace = Class(unsafe { sys::rb_const_get(namespace.0, id) });
+        }
+
+        namespace
+    }
 }
 
 pub fn inspect(val: VALUE) -> String {
     unsafe { String::from_ruby_unwrap(sys::rb_inspect(val)) }
 }
 
+/// Returns true if `value` is an instance of `class` or of one of its subclasses.
+pub fn is_kind_of(value: VALUE, class: Class) -> bool {
+    unsafe { sys::RTEST(sys::rb_obj_is_kind_of(value, class.0)) }
+}
+
+/// Returns true if `value` wraps data of `data_type`. Subclasses inherit
+/// their parent's allocator, and with it the data type, unless they define
+/// their own, so this accepts subclasses that share the wrapped struct's
+/// layout and rejects those that don't.
+pub fn is_typed_data(value: VALUE, data_type: &'static sys::rb_data_type_t) -> bool {
+    unsafe { sys::rb_typeddata_is_kind_of(value, data_type) != 0 }
+}
+
+/// Sets up the classes and hooks Helix itself needs. Called by `ruby!` when
+/// the extension is loaded.
+#[doc(hidden)]
+pub fn init() {
+    panic::install();
+    borrow::install();
+}
+
 pub unsafe fn as_usize(value: ::VALUE) -> usize {
     std::mem::transmute(value)
 }
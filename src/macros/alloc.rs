@@ -8,36 +8,252 @@ macro_rules! codegen_allocator {
         meta: $meta:tt,
         struct: (),
         methods: $methods:tt
-    }) => ();
+    }) => (
+        impl $crate::Mark for $rust_name {
+            fn mark(&self) {
+                $crate::Mark::mark(&self.helix);
+            }
+
+            fn mark_movable(&self) {
+                $crate::Mark::mark_movable(&self.helix);
+            }
+
+            fn compact(&mut self) {
+                $crate::Mark::compact(&mut self.helix);
+            }
+        }
+    );
 
     ({
         type: class,
         rust_name: $rust_name:tt,
         ruby_name: $ruby_name:tt,
         attributes: $attributes:tt,
-        meta: { pub: $pub:tt, reopen: false },
-        struct: $struct:tt,
+        meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+        struct: { $({ name: $field:ident, ty: $ty:ty, vis: $vis:tt, mark: $mark:tt, memsize: $memsize:tt, attributes: $field_attributes:tt })* },
         methods: [ $($method:tt)* ]
     }) => (
+        impl $crate::Mark for $rust_name {
+            fn mark(&self) {
+                $crate::Mark::mark(&self.helix);
+                $( codegen_mark_field!($mark, mark, self.$field); )*
+            }
+
+            fn mark_movable(&self) {
+                $crate::Mark::mark_movable(&self.helix);
+                $( codegen_mark_field!($mark, mark_movable, self.$field); )*
+            }
+
+            fn compact(&mut self) {
+                $crate::Mark::compact(&mut self.helix);
+                $( codegen_mark_field!($mark, compact, self.$field); )*
+            }
+        }
+
+        impl $crate::MemSize for $rust_name {
+            fn memsize(&self) -> usize {
+                0 $( + codegen_memsize_field!($memsize, self.$field) )*
+            }
+        }
+
         impl $rust_name {
-            extern "C" fn __mark__(_klass: &$rust_name) {}
-            extern "C" fn __free__(_klass: Option<Box<$rust_name>>) {}
+            extern "C" fn __mark__(data: *mut $crate::libc::c_void) {
+                let rust_self = unsafe { &*(data as *const $crate::Wrapped<$rust_name>) };
+                $crate::Mark::mark_movable(rust_self.get());
+            }
+
+            extern "C" fn __compact__(data: *mut $crate::libc::c_void) {
+                let rust_self = unsafe { &*(data as *const $crate::Wrapped<$rust_name>) };
+                unsafe { rust_self.compact() };
+            }
+
+            extern "C" fn __free__(data: *mut $crate::libc::c_void) {
+                if data != ::std::ptr::null_mut() {
+                    drop(unsafe { Box::from_raw(data as *mut $crate::Wrapped<$rust_name>) });
+                }
+            }
+
+            extern "C" fn __size__(data: *const $crate::libc::c_void) -> $crate::libc::size_t {
+                let rust_self = unsafe { &*(data as *const $crate::Wrapped<$rust_name>) };
+                ::std::mem::size_of::<$crate::Wrapped<$rust_name>>() + $crate::MemSize::memsize(rust_self.get())
+            }
+
+            #[inline]
+            fn __data_type__() -> &'static $crate::sys::rb_data_type_t {
+                codegen_assert_shareable!($shareable, { $($ty),* });
+
+                static DATA_TYPE: $crate::sys::rb_data_type_t = $crate::sys::rb_data_type_t {
+                    wrap_struct_name: cstr!(concat!(module_path!(), "::", stringify!($rust_name))),
+                    function: $crate::sys::rb_data_type_function_t {
+                        dmark: Some($rust_name::__mark__),
+                        dfree: Some($rust_name::__free__),
+                        dsize: Some($rust_name::__size__),
+                        dcompact: Some($rust_name::__compact__),
+                        reserved: [0 as *mut $crate::libc::c_void; 1]
+                    },
+                    parent: 0 as *const $crate::sys::rb_data_type_t,
+                    data: 0 as *mut $crate::libc::c_void,
+                    // Structs are dropped during GC sweep, so their `Drop` must not call into Ruby.
+                    flags: codegen_data_type_flags!($shareable)
+                };
+
+                &DATA_TYPE
+            }
+
+            extern "C" fn __alloc__(klass: $crate::sys::VALUE) -> $crate::sys::VALUE {
+                unsafe { $crate::sys::rb_data_typed_object_wrap(klass, ::std::ptr::null_mut(), $rust_name::__data_type__()) }
+            }
+
+            extern "C" fn __initialize_copy__(rb_self: $crate::sys::VALUE, orig: $crate::sys::VALUE) -> $crate::sys::VALUE {
+                match $rust_name::__rust_initialize_copy__(rb_self, orig) {
+                    Ok(value) => value,
+                    Err(exception) => unsafe { exception.raise() }
+                }
+            }
+
+            fn __rust_initialize_copy__(rb_self: $crate::sys::VALUE, orig: $crate::sys::VALUE) -> Result<$crate::sys::VALUE, $crate::Error> {
+                use $crate::{CopyClone, CopyUnsupported, FromRuby, sys};
+
+                let _borrows = $crate::BorrowScope::new();
+
+                // Checks that the copy isn't frozen and has the same class as the original
+                try!($crate::protect(|| unsafe { sys::rb_obj_init_copy(rb_self, orig) }));
+
+                if rb_self == orig {
+                    return Ok(rb_self);
+                }
+
+                let original = <&$rust_name>::from_checked(try!(<&$rust_name>::from_ruby(orig)));
+
+                let copy = try!(handle_exception! {
+                    (&$crate::CopyProbe::<$rust_name>::new()).copy(original)
+                });
+
+                let mut copy = match copy {
+                    Some(copy) => copy,
+                    None => {
+                        let class = unsafe { ::std::ffi::CStr::from_ptr(sys::rb_obj_classname(orig)) }.to_string_lossy();
+                        let message = format!("Cannot copy {}, it does not implement Clone", class);
+                        type_error!(message);
+                    }
+                };
+
+                copy.helix = rb_self;
+
+                // Set when `initialize_copy` is called again on an existing copy
+                let previous: Option<Box<$crate::Wrapped<$rust_name>>> = unsafe { ::std::mem::transmute(sys::Data_Get_Struct_Value(rb_self)) };
+
+                // Replacing the struct while it is borrowed would leave the borrow dangling
+                if let Some(Err(error)) = previous.as_ref().map(|previous| previous.check_unborrowed(rb_self)) {
+                    ::std::mem::forget(previous);
+                    return Err(error);
+                }
+
+                let data = Box::into_raw(Box::new($crate::Wrapped::new(copy)));
+                unsafe { sys::Data_Set_Struct_Value(rb_self, data as *mut $crate::libc::c_void) };
+
+                drop(previous);
+
+                Ok(rb_self)
+            }
 
             #[inline]
             fn __alloc_with__(rust_self: Option<Box<$rust_name>>) -> $crate::sys::VALUE {
-                use ::std::mem::transmute;
+                let data = match rust_self {
+                    Some(rust_self) => Box::into_raw(Box::new($crate::Wrapped::new(*rust_self))) as *mut $crate::libc::c_void,
+                    None => ::std::ptr::null_mut()
+                };
 
                 unsafe {
-                    let instance = $crate::sys::Data_Wrap_Struct(
-                        transmute($rust_name),
-                        transmute($rust_name::__mark__ as usize),
-                        transmute($rust_name::__free__ as usize),
-                        transmute(rust_self)
-                    );
-
-                    instance
+                    let klass = ::std::mem::transmute($rust_name);
+                    $crate::sys::rb_data_typed_object_wrap(klass, data, $rust_name::__data_type__())
                 }
             }
         }
+
+        codegen_marshal!($rust_name, $marshal);
     )
 }
+
+#[macro_export]
+macro_rules! codegen_marshal {
+    ($rust_name:tt, false) => {};
+
+    ($rust_name:tt, true) => {
+        impl $rust_name {
+            extern "C" fn __dump__(rb_self: $crate::sys::VALUE, _level: $crate::sys::VALUE) -> $crate::sys::VALUE {
+                match $rust_name::__rust_dump__(rb_self) {
+                    Ok(value) => value,
+                    Err(exception) => unsafe { exception.raise() }
+                }
+            }
+
+            fn __rust_dump__(rb_self: $crate::sys::VALUE) -> Result<$crate::sys::VALUE, $crate::Error> {
+                use $crate::FromRuby;
+
+                let _borrows = $crate::BorrowScope::new();
+
+                let rust_self = <&$rust_name>::from_checked(try!(<&$rust_name>::from_ruby(rb_self)));
+
+                try!(handle_exception! {
+                    $crate::marshal_dump($rust_name::__data_type__(), rust_self)
+                })
+            }
+
+            extern "C" fn __load__(klass: $crate::sys::VALUE, data: $crate::sys::VALUE) -> $crate::sys::VALUE {
+                match $rust_name::__rust_load__(klass, data) {
+                    Ok(value) => value,
+                    Err(exception) => unsafe { exception.raise() }
+                }
+            }
+
+            fn __rust_load__(klass: $crate::sys::VALUE, data: $crate::sys::VALUE) -> Result<$crate::sys::VALUE, $crate::Error> {
+                let mut rust_self: $rust_name = try!(try!(handle_exception! {
+                    $crate::marshal_load($rust_name::__data_type__(), data)
+                }));
+
+                // `klass` is the class being loaded, which can be a subclass
+                let rb_self = $rust_name::__alloc__(klass);
+                rust_self.helix = rb_self;
+
+                let data = Box::into_raw(Box::new($crate::Wrapped::new(rust_self)));
+                unsafe { $crate::sys::Data_Set_Struct_Value(rb_self, data as *mut $crate::libc::c_void) };
+
+                Ok(rb_self)
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! codegen_mark_field {
+    (true, compact, $field:expr) => { $crate::Mark::compact(&mut $field) };
+    (true, $method:ident, $field:expr) => { $crate::Mark::$method(&$field) };
+    (false, $method:ident, $field:expr) => {};
+}
+
+#[macro_export]
+macro_rules! codegen_memsize_field {
+    (true, $field:expr) => { $crate::MemSize::memsize(&$field) };
+    (false, $field:expr) => { 0 };
+}
+
+#[macro_export]
+macro_rules! codegen_data_type_flags {
+    (false) => { $crate::sys::RUBY_TYPED_FREE_IMMEDIATELY };
+
+    // Lets `Ractor.make_shareable` share frozen objects
+    (true) => { $crate::sys::RUBY_TYPED_FREE_IMMEDIATELY_FROZEN_SHAREABLE };
+}
+
+#[macro_export]
+macro_rules! codegen_assert_shareable {
+    (false, $types:tt) => {};
+
+    // A shared object can be used, and freed, on any Ractor's thread. The
+    // fields are checked rather than the struct, as `helix` is a raw VALUE.
+    (true, { $($ty:ty),* }) => {
+        fn assert_shareable<T: Send + Sync>() {}
+        $( assert_shareable::<$ty>(); )*
+    };
+}
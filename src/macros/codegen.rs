@@ -32,7 +32,7 @@ macro_rules! codegen {
                 rust_name: $rust_name:tt,
                 ruby_name: $ruby_name:tt,
                 attributes: $attributes:tt,
-                meta: { pub: $pub:tt, reopen: $reopen:tt },
+                meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
                 struct: $struct:tt,
                 methods: [ $($method:tt)* ]
             }
@@ -46,7 +46,7 @@ macro_rules! codegen {
                 {
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
-                    struct: { codegen_struct! { pub: $pub, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: $struct } },
+                    struct: { codegen_struct! { pub: $pub, marshal: $marshal, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: $struct } },
                     methods: [ $( codegen_method! { $method } )* ]
                 }
             ],
@@ -58,12 +58,78 @@ macro_rules! codegen {
             rust_name: $rust_name,
             ruby_name: $ruby_name,
             attributes: $attributes,
-            meta: { pub: $pub, reopen: $reopen },
+            meta: { pub: $pub, reopen: $reopen, namespace: $namespace, superclass: $superclass, marshal: $marshal, shareable: $shareable },
             struct: $struct,
             methods: [ $($method)* ]
         });
     };
 
+    {
+        type: top,
+        classes: [
+            $($class:tt)*
+        ],
+        buffer: [
+            {
+                type: module,
+                rust_name: $rust_name:tt,
+                ruby_name: $ruby_name:tt,
+                attributes: $attributes:tt,
+                meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: (), marshal: $marshal:tt, shareable: $shareable:tt },
+                struct: (),
+                methods: [ $($method:tt)* ]
+            }
+            $($rest:tt)*
+        ]
+    } => {
+        codegen! {
+            type: top,
+            classes: [
+                $($class)*
+                {
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    struct: { codegen_struct! { pub: $pub, marshal: false, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: () } },
+                    methods: [ $( codegen_method! { $method } )* ]
+                }
+            ],
+            buffer: [ $($rest)* ]
+        }
+    };
+
+    {
+        type: top,
+        classes: [
+            $($class:tt)*
+        ],
+        buffer: [
+            {
+                type: exception,
+                rust_name: $rust_name:tt,
+                ruby_name: $ruby_name:tt,
+                attributes: $attributes:tt,
+                meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+                struct: (),
+                methods: []
+            }
+            $($rest:tt)*
+        ]
+    } => {
+        codegen! {
+            type: top,
+            classes: [
+                $($class)*
+                {
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    struct: { codegen_exception! { pub: $pub, rust_name: $rust_name, attributes: $attributes } },
+                    methods: []
+                }
+            ],
+            buffer: [ $($rest)* ]
+        }
+    };
+
     {
         type: done,
         classes: [ $(
@@ -89,11 +155,11 @@ macro_rules! codegen {
 macro_rules! codegen_pub_classes {
     {
             $({
-                type: class,
+                type: $type:tt,
                 rust_name: $rust_name:tt,
                 ruby_name: $ruby_name:tt,
                 attributes: $attributes:tt,
-                meta: { pub: $pub:tt, reopen: $reopen:tt },
+                meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
                 struct: $struct:tt,
                 methods: [ $($method:tt)* ]
             })*
@@ -125,36 +191,85 @@ macro_rules! codegen_pub_classes {
     };
 }
 
+#[macro_export]
+macro_rules! codegen_exception {
+    { pub: false, rust_name: $rust_name:tt, attributes: $attributes:tt } => {
+        codegen_exception! { pub: {}, rust_name: $rust_name, attributes: $attributes }
+    };
+
+    { pub: true, rust_name: $rust_name:tt, attributes: $attributes:tt } => {
+        codegen_exception! { pub: { pub }, rust_name: $rust_name, attributes: $attributes }
+    };
+
+    { pub: { $($pub:tt)* }, rust_name: $rust_name:tt, attributes: { $($attributes:tt)* } } => {
+        #[derive(Copy, Clone, Debug)]
+        $($attributes)*
+        $($pub)* struct $rust_name;
+
+        impl $rust_name {
+            #[doc(hidden)]
+            pub fn __class__(define: Option<$crate::Class>) -> $crate::Class {
+                static mut CLASS: Option<$crate::Class> = None;
+
+                unsafe {
+                    if define.is_some() {
+                        CLASS = define;
+                    }
+
+                    CLASS.expect(concat!("`", stringify!($rust_name), "` is only defined once the extension is loaded"))
+                }
+            }
+        }
+
+        impl ::std::convert::From<$rust_name> for $crate::Class {
+            fn from(_: $rust_name) -> $crate::Class {
+                $rust_name::__class__(None)
+            }
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! codegen_struct {
-    { pub: false, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: () } => {
-        codegen_struct! { pub: {}, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: {} }
+    { pub: $pub:tt, marshal: $marshal:tt, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: () } => {
+        codegen_struct! { pub: $pub, marshal: false, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: {} }
+    };
+
+    { pub: false, marshal: $marshal:tt, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: $struct:tt } => {
+        codegen_struct! { pub: {}, marshal: $marshal, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: $struct }
     };
 
-    { pub: true, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: () } => {
-        codegen_struct! { pub: { pub }, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: {} }
+    { pub: true, marshal: $marshal:tt, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: $struct:tt } => {
+        codegen_struct! { pub: { pub }, marshal: $marshal, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: $struct }
     };
 
-    { pub: false, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: { $($rest:tt)* } } => {
-        codegen_struct! { pub: {}, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: { $($rest)* } }
+    { pub: $pub:tt, marshal: false, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: $struct:tt } => {
+        codegen_struct! { pub: $pub, helix_attributes: {}, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: $struct }
     };
 
-    { pub: true, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: { $($rest:tt)* } } => {
-        codegen_struct! { pub: { pub }, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: { $($rest)* } }
+    // The Ruby object isn't part of the dumped data, `_load` allocates a new one.
+    // serde takes the default as a path in a string, so it can't use `$crate`.
+    { pub: $pub:tt, marshal: true, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, attributes: $attributes:tt, struct: $struct:tt } => {
+        codegen_struct! { pub: $pub, helix_attributes: { #[serde(skip, default = "::helix::marshal_nil")] }, rust_name: $rust_name, ruby_name: $ruby_name, attributes: $attributes, struct: $struct }
     };
 
     {
         pub: { $($pub:tt)* },
+        helix_attributes: { $($helix_attributes:tt)* },
         rust_name: $rust_name:tt,
         ruby_name: $ruby_name:tt,
         attributes: { $($attributes:tt)* },
-        struct: { $($struct:tt)* }
+        struct: { $({ name: $field:ident, ty: $ty:ty, vis: { $($vis:tt)* }, mark: $mark:tt, memsize: $memsize:tt, attributes: { $($field_attributes:tt)* } })* }
     } => {
         #[repr(C)]
         $($attributes)*
         $($pub)* struct $rust_name {
+            $($helix_attributes)*
             helix: $crate::Metadata,
-            $($struct)*
+            $(
+                $($field_attributes)*
+                $($vis)* $field: $ty,
+            )*
         }
 
         #[allow(non_upper_case_globals)]
@@ -170,18 +285,21 @@ macro_rules! codegen_method {
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: { $($attributes:tt)* },
             self: {
                 ownership: {},
                 name: $self:tt
             },
-            args: [ $($arg:tt : $argty:ty),* ],
+            args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+            block: [ $({ name: $block:tt, optional: $optional:tt })* ],
             ret: { $($ret:tt)* },
             body: $body:block
         }
     } => {
         $($attributes)*
-        pub fn $rust_name($self : $crate::Metadata, $($arg : $argty),*) -> $($ret)* $body
+        pub fn $rust_name($self : $crate::Metadata, $($arg : $argty,)* $($block : codegen_block_type!($optional),)*) -> $($ret)* $body
     };
 
     {
@@ -190,15 +308,18 @@ macro_rules! codegen_method {
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: { $($attributes:tt)* },
             self: (),
-            args: [ $($args:tt)* ],
+            args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+            block: [ $({ name: $block:tt, optional: $optional:tt })* ],
             ret: { $($ret:tt)* },
             body: $body:block
         }
     } => {
         $($attributes)*
-        pub fn $rust_name($($args)*) -> $($ret)* $body
+        pub fn $rust_name($($arg : $argty,)* $($block : codegen_block_type!($optional),)*) -> $($ret)* $body
     };
 
     {
@@ -207,21 +328,30 @@ macro_rules! codegen_method {
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: { $($attributes:tt)* },
             self: {
                 ownership: { $($ownership:tt)* },
                 name: $self:tt
             },
-            args: [ $($args:tt)* ],
+            args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+            block: [ $({ name: $block:tt, optional: $optional:tt })* ],
             ret: { $($ret:tt)* },
             body: $body:block
         }
     } => {
         $($attributes)*
-        pub fn $rust_name($($ownership)* $self, $($args)*) -> $($ret)* $body
+        pub fn $rust_name($($ownership)* $self, $($arg : $argty,)* $($block : codegen_block_type!($optional),)*) -> $($ret)* $body
     };
 }
 
+#[macro_export]
+macro_rules! codegen_block_type {
+    (false) => { $crate::Block };
+    (true) => { Option<$crate::Block> };
+}
+
 #[macro_export]
 macro_rules! codegen_extra_impls {
     ($class:tt) => (
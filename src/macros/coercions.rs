@@ -5,7 +5,7 @@ macro_rules! codegen_coercions {
         rust_name: $rust_name:tt,
         ruby_name: $ruby_name:tt,
         attributes: $attributes:tt,
-        meta: { pub: $pub:tt, reopen: $reopen:tt },
+        meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
         struct: (),
         methods: $methods:tt
     }) => (
@@ -16,7 +16,7 @@ macro_rules! codegen_coercions {
                 use $crate::{CheckedValue, sys};
                 use ::std::ffi::{CStr};
 
-                if unsafe { $rust_name == $crate::as_usize(sys::rb_obj_class(value)) } {
+                if $crate::is_kind_of(value, unsafe { $crate::Class::from_value(::std::mem::transmute($rust_name)) }) {
                     Ok(unsafe { CheckedValue::new(value) })
                 } else {
                     let val = unsafe { CStr::from_ptr(sys::rb_obj_classname(value)).to_string_lossy() };
@@ -38,38 +38,52 @@ macro_rules! codegen_coercions {
         rust_name: $rust_name:tt,
         ruby_name: $ruby_name:tt,
         attributes: $attributes:tt,
-        meta: { pub: $pub:tt, reopen: false },
+        meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
         struct: $struct:tt,
         methods: $methods:tt
     }) => (
         impl $crate::FromRuby for $rust_name {
-            type Checked = Box<$rust_name>;
+            type Checked = $crate::CheckedValue<$rust_name>;
 
-            fn from_ruby(value: $crate::sys::VALUE) -> $crate::CheckResult<Box<$rust_name>> {
+            fn from_ruby(value: $crate::sys::VALUE) -> $crate::CheckResult<$crate::CheckedValue<$rust_name>> {
                 use $crate::{ToError, sys};
 
-                if unsafe { $rust_name != $crate::as_usize(sys::rb_obj_class(value)) } {
+                if !$crate::is_typed_data(value, $rust_name::__data_type__()) {
                     type_error!(value, stringify!($rust_name));
                 }
 
+                try!($crate::check_poisoned(value));
+
                 let ptr = unsafe { sys::Data_Get_Struct_Value(value) };
 
-                if ptr != ::std::ptr::null_mut() {
-                    Ok(unsafe { ::std::mem::transmute(ptr) })
-                } else {
-                    Err(format!("Uninitialized {}", stringify!($rust_name)).to_error())
+                if ptr == ::std::ptr::null_mut() {
+                    return Err(format!("Uninitialized {}", stringify!($rust_name)).to_error());
                 }
+
+                codegen_check_shared_frozen!($shareable, value);
+
+                // Moving the struct out while it is borrowed would leave the borrow dangling
+                try!(unsafe { $crate::Wrapped::<$rust_name>::reserve(ptr as *mut _, value) });
+
+                Ok(unsafe { $crate::CheckedValue::new(value) })
             }
 
-            fn from_checked(mut checked: Box<$rust_name>) -> $rust_name {
-                unsafe { $crate::sys::Data_Set_Struct_Value(checked.helix, ::std::ptr::null_mut()) };
-                checked.helix = unsafe { $crate::sys::Qnil };
-                *checked
+            fn from_checked(checked: $crate::CheckedValue<$rust_name>) -> $rust_name {
+                let value = checked.to_value();
+
+                let mut rust_self = unsafe {
+                    let ptr = $crate::sys::Data_Get_Struct_Value(value);
+                    $crate::sys::Data_Set_Struct_Value(value, ::std::ptr::null_mut());
+                    $crate::Wrapped::<$rust_name>::take(ptr as *mut _)
+                };
+
+                rust_self.helix = unsafe { $crate::sys::Qnil };
+                rust_self
             }
         }
 
-        impl_struct_to_rust!(&'a $rust_name, $rust_name);
-        impl_struct_to_rust!(&'a mut $rust_name, $rust_name);
+        impl_struct_to_rust!(&'a $rust_name, $rust_name, borrow, false);
+        impl_struct_to_rust!(&'a mut $rust_name, $rust_name, borrow_mut, $shareable);
 
         impl $crate::ToRuby for $rust_name {
             fn to_ruby(self) -> $crate::ToRubyResult {
@@ -84,21 +98,24 @@ macro_rules! codegen_coercions {
 
 #[macro_export]
 macro_rules! impl_struct_to_rust {
-    ($rust_name:ty, $helix_id:tt) => {
+    ($rust_name:ty, $helix_id:tt, $borrow:ident, $check_frozen:tt) => {
         impl<'a> $crate::FromRuby for $rust_name {
             type Checked = $rust_name;
 
             fn from_ruby(value: $crate::sys::VALUE) -> $crate::CheckResult<$rust_name> {
                 use $crate::{ToError, sys};
 
-                if unsafe { $helix_id != $crate::as_usize(sys::rb_obj_class(value)) } {
+                if !$crate::is_typed_data(value, $helix_id::__data_type__()) {
                     type_error!(value, stringify!($helix_id));
                 }
 
+                try!($crate::check_poisoned(value));
+                codegen_check_shared_frozen!($check_frozen, value);
+
                 let ptr = unsafe { sys::Data_Get_Struct_Value(value) };
 
                 if ptr != ::std::ptr::null_mut() {
-                    Ok(unsafe { ::std::mem::transmute(ptr) })
+                    unsafe { $crate::Wrapped::<$helix_id>::$borrow(ptr as *mut _, value) }
                 } else {
                     Err(format!("Uninitialized {}", stringify!($helix_id)).to_error())
                 }
@@ -111,6 +128,15 @@ macro_rules! impl_struct_to_rust {
     }
 }
 
+// Frozen objects of `#[ruby_shareable]` classes can be used from several
+// Ractors at once, so they can't be mutated or moved out of, not even as
+// arguments. `&mut self` and `self` receivers are checked with the others.
+#[macro_export]
+macro_rules! codegen_check_shared_frozen {
+    (false, $value:ident) => {};
+    (true, $value:ident) => { try!($crate::check_frozen($value)); };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! impl_to_ruby {
@@ -5,6 +5,7 @@ macro_rules! codegen_init {
         #[no_mangle]
         pub extern "C" fn Init_native() {
             $crate::sys::check_version();
+            $crate::init();
 
             $(
                 codegen_class_binding!($class, $class);
@@ -20,12 +21,13 @@ macro_rules! codegen_class_binding {
         rust_name: $rust_name:tt,
         ruby_name: { $($ruby_name:tt)* },
         attributes: $attributes:tt,
-        meta: { pub: $pub:tt, reopen: false },
+        meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
         struct: (),
         methods: [ $($method:tt)* ]
     } } => ({
         use ::std::mem::transmute;
-        let def = $crate::ClassDefinition::new(cstr!($($ruby_name)*));
+        let namespace = codegen_namespace!($namespace);
+        let def = $crate::ClassDefinition::new_under(namespace, cstr!($($ruby_name)*), codegen_superclass!(namespace, $superclass));
 
         $(
             codegen_define_method!(def, $class, $method);
@@ -39,12 +41,12 @@ macro_rules! codegen_class_binding {
         rust_name: $rust_name:tt,
         ruby_name: { $($ruby_name:tt)* },
         attributes: $attributes:tt,
-        meta: { pub: $pub:tt, reopen: true },
+        meta: { pub: $pub:tt, reopen: true, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
         struct: (),
         methods: [ $($method:tt)* ]
     } } => ({
         use ::std::mem::transmute;
-        let def = $crate::ClassDefinition::reopen(cstr!($($ruby_name)*));
+        let def = $crate::ClassDefinition::reopen_under(codegen_namespace!($namespace), cstr!($($ruby_name)*));
 
         $(
             codegen_define_method!(def, $class, $method);
@@ -58,17 +60,21 @@ macro_rules! codegen_class_binding {
         rust_name: $rust_name:tt,
         ruby_name: { $($ruby_name:tt)* },
         attributes: $attributes:tt,
-        meta: { pub: $pub:tt, reopen: $reopen:tt },
+        meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
         struct: { $($struct:tt)* },
         methods: [ $($method:tt)* ]
     } } => ({
         use ::std::mem::transmute;
 
-        extern "C" fn __alloc__(_klass: $crate::sys::VALUE) -> $crate::sys::VALUE {
-            $rust_name::__alloc_with__(None)
-        }
+        let namespace = codegen_namespace!($namespace);
+        let def = $crate::ClassDefinition::wrapped_under(namespace, cstr!($($ruby_name)*), codegen_superclass!(namespace, $superclass), $rust_name::__alloc__);
+
+        // Makes `dup` and `clone` copy the wrapped struct, defined first so
+        // the class can still override it
+        let initialize_copy = $rust_name::__initialize_copy__ as *const $crate::libc::c_void;
+        def.define_method($crate::MethodDefinition::instance(cstr!("initialize_copy"), initialize_copy, 1, $crate::Visibility::Private));
 
-        let def = $crate::ClassDefinition::wrapped(cstr!($($ruby_name)*), __alloc__);
+        codegen_define_marshal!(def, $rust_name, $marshal);
 
         $(
             codegen_define_method!(def, $class, $method);
@@ -77,12 +83,85 @@ macro_rules! codegen_class_binding {
         unsafe { $rust_name = transmute(def.class) }
     });
 
+    { $class:tt, {
+        type: exception,
+        rust_name: $rust_name:tt,
+        ruby_name: { $($ruby_name:tt)* },
+        attributes: $attributes:tt,
+        meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+        struct: (),
+        methods: []
+    } } => ({
+        let namespace = codegen_namespace!($namespace);
+        let def = $crate::ClassDefinition::new_under(namespace, cstr!($($ruby_name)*), codegen_superclass!(namespace, $superclass));
+
+        $rust_name::__class__(Some(def.class));
+    });
+
+    { $class:tt, {
+        type: module,
+        rust_name: $rust_name:tt,
+        ruby_name: { $($ruby_name:tt)* },
+        attributes: $attributes:tt,
+        meta: { pub: $pub:tt, reopen: false, namespace: $namespace:tt, superclass: (), marshal: $marshal:tt, shareable: $shareable:tt },
+        struct: (),
+        methods: [ $($method:tt)* ]
+    } } => ({
+        use ::std::mem::transmute;
+        let def = $crate::ClassDefinition::module_under(codegen_namespace!($namespace), cstr!($($ruby_name)*));
+
+        $(
+            codegen_define_method!(def, $class, $method);
+        )*
+
+        unsafe { $rust_name = transmute(def.class) };
+    });
+
+}
+
+#[macro_export]
+macro_rules! codegen_define_marshal {
+    ($def:ident, $rust_name:tt, false) => {};
+
+    // Private like Ruby's own `_dump` and `_load`, `Marshal` calls them anyway
+    ($def:ident, $rust_name:tt, true) => {
+        let dump = $rust_name::__dump__ as *const $crate::libc::c_void;
+        $def.define_method($crate::MethodDefinition::instance(cstr!("_dump"), dump, 1, $crate::Visibility::Private));
+
+        let load = $rust_name::__load__ as *const $crate::libc::c_void;
+        $def.define_method($crate::MethodDefinition::class(cstr!("_load"), load, 1, $crate::Visibility::Private));
+    };
+}
+
+#[macro_export]
+macro_rules! codegen_namespace {
+    ([ $({ $($ruby_name:tt)* })* ]) => ({
+        #[allow(unused_mut)]
+        let mut namespace = unsafe { $crate::Class::from_value($crate::sys::rb_cObject) };
+
+        $(
+            namespace = $crate::ClassDefinition::module_under(namespace, cstr!($($ruby_name)*)).class;
+        )*
+
+        namespace
+    });
+}
+
+#[macro_export]
+macro_rules! codegen_superclass {
+    ($namespace:ident, ()) => {
+        unsafe { $crate::Class::from_value($crate::sys::rb_cObject) }
+    };
+
+    ($namespace:ident, { $superclass:tt }) => {
+        $namespace.lookup(stringify!($superclass))
+    };
 }
 
 #[macro_export]
 macro_rules! codegen_define_method {
     ($def:tt, {
-        type: class,
+        type: $cls_type:tt,
         rust_name: $cls_rust_name:tt,
         $($rest:tt)*
     }, {
@@ -90,49 +169,62 @@ macro_rules! codegen_define_method {
         rust_name: $rust_name:tt,
         ruby_name: { $($ruby_name:tt)* },
         ruby_visibility: $ruby_visibility:tt,
+        ruby_nogvl: $ruby_nogvl:tt,
+        ruby_allow_frozen: $ruby_allow_frozen:tt,
         attributes: $attributes:tt,
         self: (),
-        args: [ $($arg:tt : $argty:ty),* ],
+        args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+        block: [ $({ name: $block:tt, optional: $optional:tt })* ],
         ret: { $($ret:tt)* },
         body: $body:tt
     }) => ({
         use $crate::sys::{VALUE};
         use $crate::{Error};
 
-        extern "C" fn __ruby_method__(_: VALUE, $($arg : VALUE),*) -> VALUE {
-            let result = __rust_method__($($arg),*);
-
-            match result {
-                Ok(value) => return value,
-                Err(exception) => unsafe { exception.raise() }
-            }
-        }
+        codegen_ruby_entry!(__ruby_method__, __rust_method__, [ $({ name: $arg, ty: $argty, kind: $kind })* ]);
 
         #[inline]
-        fn __rust_method__($($arg : $crate::sys::VALUE),*) -> Result<VALUE, Error> {
+        fn __rust_method__(_rb_self: VALUE, args: &[VALUE]) -> Result<VALUE, Error> {
             #[allow(unused_imports)]
             use $crate::{FromRuby, ToRuby};
 
+            let _borrows = $crate::BorrowScope::new();
+
+            let keyword_names = [ $(codegen_keyword!($arg, $kind)),* ];
+            #[allow(unused_variables)]
+            let (args, keywords) = $crate::Keywords::extract(args, &keyword_names);
+
+            // Like Ruby, the positional arguments are checked before the keywords
+            try!(codegen_check_arity!(args, [ $({ name: $arg, ty: $argty, kind: $kind })* ]));
+            try!(keywords.check(&keyword_names));
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            let mut index = 0;
+
             $(
-                let $arg = try!(<$argty>::from_ruby($arg));
+                let $arg = codegen_check_arg!(args, index, keywords, $arg, $argty, $kind);
             )*
 
             $(
-                let $arg = <$argty>::from_checked($arg);
+                let $arg = codegen_checked_arg!($arg, $argty, $kind);
             )*
 
-            let result: Result<$($ret)*, Error> = handle_exception! {
-                $cls_rust_name::$rust_name($($arg),*)
-            };
+            $(
+                let $block = try!(codegen_block!($optional));
+            )*
+
+            let result: Result<$($ret)*, Error> = codegen_method_call!($ruby_nogvl, {
+                $cls_rust_name::$rust_name($($arg,)* $($block,)*)
+            });
 
             result.and_then(ToRuby::to_ruby)
         }
 
         let name = cstr!($($ruby_name)*);
         let method = __ruby_method__ as *const $crate::libc::c_void;
-        let arity = method_arity!($($arg)*);
+        let arity = codegen_method_arity!([ $({ name: $arg, ty: $argty, kind: $kind })* ]);
 
-        $def.define_method($crate::MethodDefinition::class(name, method, arity));
+        $def.define_method(codegen_singleton_method_definition!($cls_type, $ruby_visibility, name, method, arity));
     });
 
     ($def:tt, {
@@ -148,53 +240,72 @@ macro_rules! codegen_define_method {
         rust_name: $rust_name:tt,
         ruby_name: { $($ruby_name:tt)* },
         ruby_visibility: $ruby_visibility:tt,
+        ruby_nogvl: $ruby_nogvl:tt,
+        ruby_allow_frozen: $ruby_allow_frozen:tt,
         attributes: $metohd_attributes:tt,
         self: { ownership: { $($ownership:tt)* }, name: $self:tt },
-        args: [ $($arg:tt : $argty:ty),* ],
+        args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+        block: [ $({ name: $block:tt, optional: $optional:tt })* ],
         ret: { $($ret:tt)* },
         body: $body:tt
     }) => ({
         use $crate::sys::{VALUE};
         use $crate::{Error};
 
-        extern "C" fn __ruby_method__(rb_self: VALUE, $($arg : VALUE),*) -> VALUE {
-            let result = __rust_method__(rb_self, $($arg),*);
-
-            match result {
-                Ok(value) => return value,
-                Err(exception) => unsafe { exception.raise() }
-            }
-        }
+        codegen_ruby_entry!(__ruby_method__, __rust_method__, [ $({ name: $arg, ty: $argty, kind: $kind })* ]);
 
         #[inline]
-        fn __rust_method__(rb_self: VALUE, $($arg : VALUE),*) -> Result<VALUE, Error> {
+        fn __rust_method__(rb_self: VALUE, args: &[VALUE]) -> Result<VALUE, Error> {
             #[allow(unused_imports)]
             use $crate::{FromRuby, ToRuby};
 
+            let _borrows = $crate::BorrowScope::new();
+
+            let keyword_names = [ $(codegen_keyword!($arg, $kind)),* ];
+            #[allow(unused_variables)]
+            let (args, keywords) = $crate::Keywords::extract(args, &keyword_names);
+
+            // Like Ruby, the positional arguments are checked before the keywords
+            try!(codegen_check_arity!(args, [ $({ name: $arg, ty: $argty, kind: $kind })* ]));
+            try!(keywords.check(&keyword_names));
+
+            codegen_check_frozen!($struct, { $($ownership)* }, $ruby_allow_frozen, rb_self);
+
             let rust_self = try!(<codegen_self_pointer_type! { struct: $struct, ownership: { $($ownership)* }, type: $cls_rust_name }>::from_ruby(rb_self));
 
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            let mut index = 0;
+
             $(
-                let $arg = try!(<$argty>::from_ruby($arg));
+                let $arg = codegen_check_arg!(args, index, keywords, $arg, $argty, $kind);
             )*
 
             let rust_self = <codegen_self_pointer_type! { struct: $struct, ownership: { $($ownership)* }, type: $cls_rust_name }>::from_checked(rust_self);
 
             $(
-                let $arg = <$argty>::from_checked($arg);
+                let $arg = codegen_checked_arg!($arg, $argty, $kind);
             )*
 
-            let result: Result<$($ret)*, Error> = handle_exception! {
-                rust_self.$rust_name($($arg),*)
+            $(
+                let $block = try!(codegen_block!($optional));
+            )*
+
+            let result = catch_panic! {
+                rust_self.$rust_name($($arg,)* $($block,)*)
             };
 
+            codegen_poison_on_panic!($struct, { $($ownership)* }, rb_self, result, $($ruby_name)*);
+
+            let result: Result<$($ret)*, Error> = result.map_err(|e| $crate::Error::from_any(e));
+
             result.and_then(ToRuby::to_ruby)
         }
 
         let name = cstr!($($ruby_name)*);
         let method = __ruby_method__ as *const $crate::libc::c_void;
-        let arity = method_arity!($($arg)*);
+        let arity = codegen_method_arity!([ $({ name: $arg, ty: $argty, kind: $kind })* ]);
 
-        $def.define_method($crate::MethodDefinition::instance(name, method, arity))
+        $def.define_method($crate::MethodDefinition::instance(name, method, arity, codegen_visibility!($ruby_visibility)))
     });
 
     ($def:tt, {
@@ -206,15 +317,18 @@ macro_rules! codegen_define_method {
         rust_name: $rust_name:tt,
         ruby_name: $ruby_name:tt,
         ruby_visibility: unexported,
+        ruby_nogvl: $ruby_nogvl:tt,
+        ruby_allow_frozen: $ruby_allow_frozen:tt,
         attributes: $attributes:tt,
         self: $self:tt,
-        args: [ $($arg:tt : $argty:ty),* ],
+        args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+        block: [ $({ name: $block:tt, optional: $optional:tt })* ],
         ret: { $($ret:tt)* },
         body: $body:tt
     }) => ({
         impl $cls_rust_name {
-            pub fn new($($arg : $argty),*) -> $($ret)* {
-                $cls_rust_name::$rust_name(unsafe { $crate::sys::Qnil } , $($arg),*)
+            pub fn new($($arg : $argty,)* $($block : codegen_block_type!($optional),)*) -> $($ret)* {
+                $cls_rust_name::$rust_name(unsafe { $crate::sys::Qnil }, $($arg,)* $($block,)*)
             }
         }
 
@@ -234,9 +348,12 @@ macro_rules! codegen_define_method {
         rust_name: $rust_name:tt,
         ruby_name: { $($ruby_name:tt)* },
         ruby_visibility: $ruby_visibility:tt,
+        ruby_nogvl: $ruby_nogvl:tt,
+        ruby_allow_frozen: $ruby_allow_frozen:tt,
         attributes: $methohd_attributes:tt,
         self: { ownership: {}, name: $self:tt },
-        args: [ $($arg:tt : $argty:ty),* ],
+        args: [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ],
+        block: [ $({ name: $block:tt, optional: $optional:tt })* ],
         ret: { $($ret:tt)* },
         body: $body:tt
     }) => ({
@@ -244,48 +361,249 @@ macro_rules! codegen_define_method {
         use $crate::{Error};
 
         impl $cls_rust_name {
-            pub fn new($($arg : $argty),*) -> $($ret)* {
-                $cls_rust_name::$rust_name(unsafe { $crate::sys::Qnil } , $($arg),*)
+            pub fn new($($arg : $argty,)* $($block : codegen_block_type!($optional),)*) -> $($ret)* {
+                $cls_rust_name::$rust_name(unsafe { $crate::sys::Qnil }, $($arg,)* $($block,)*)
             }
         }
 
-        extern "C" fn __ruby_initialize__(rb_self: VALUE, $($arg : VALUE),*) -> VALUE {
-            let result = __rust_initialize__(rb_self $(, $arg)*);
-
-            match result {
-                Ok(value) => return value,
-                Err(exception) => unsafe { exception.raise() }
-            }
-        }
+        codegen_ruby_entry!(__ruby_initialize__, __rust_initialize__, [ $({ name: $arg, ty: $argty, kind: $kind })* ]);
 
         #[inline]
-        fn __rust_initialize__(rb_self: VALUE, $($arg : VALUE),*) -> Result<VALUE, Error> {
+        fn __rust_initialize__(rb_self: VALUE, args: &[VALUE]) -> Result<VALUE, Error> {
             #[allow(unused_imports)]
             use $crate::{FromRuby};
             use $crate::sys::{Data_Set_Struct_Value};
 
+            let _borrows = $crate::BorrowScope::new();
+
+            let keyword_names = [ $(codegen_keyword!($arg, $kind)),* ];
+            #[allow(unused_variables)]
+            let (args, keywords) = $crate::Keywords::extract(args, &keyword_names);
+
+            // Like Ruby, the positional arguments are checked before the keywords
+            try!(codegen_check_arity!(args, [ $({ name: $arg, ty: $argty, kind: $kind })* ]));
+            try!(keywords.check(&keyword_names));
+
+            #[allow(unused_mut, unused_variables, unused_assignments)]
+            let mut index = 0;
+
+            $(
+                let $arg = codegen_check_arg!(args, index, keywords, $arg, $argty, $kind);
+            )*
+
             $(
-                let $arg = try!(<$argty>::from_ruby($arg));
+                let $arg = codegen_checked_arg!($arg, $argty, $kind);
             )*
 
             $(
-                let $arg = <$argty>::from_checked($arg);
+                let $block = try!(codegen_block!($optional));
             )*
 
-            let rust_self = Box::new($cls_rust_name::initialize(rb_self, $($arg),*));
+            let rust_self = Box::new($crate::Wrapped::new(try!(handle_exception! {
+                $cls_rust_name::initialize(rb_self, $($arg,)* $($block,)*)
+            })));
 
             unsafe { Data_Set_Struct_Value(rb_self, ::std::mem::transmute(rust_self)) };
 
             Ok(rb_self)
         }
 
-        let arity = method_arity!($($arg)*);
+        let arity = codegen_method_arity!([ $({ name: $arg, ty: $argty, kind: $kind })* ]);
         let method = __ruby_initialize__ as *const $crate::libc::c_void;
 
-        $def.define_method($crate::MethodDefinition::instance(cstr!($($ruby_name)*), method, arity));
+        $def.define_method($crate::MethodDefinition::instance(cstr!($($ruby_name)*), method, arity, $crate::Visibility::Public));
+    });
+}
+
+
+#[macro_export]
+macro_rules! codegen_singleton_method_definition {
+    (class, $visibility:tt, $name:expr, $method:expr, $arity:expr) => {
+        $crate::MethodDefinition::class($name, $method, $arity, codegen_visibility!($visibility))
+    };
+
+    (module, private, $name:expr, $method:expr, $arity:expr) => {
+        $crate::MethodDefinition::class($name, $method, $arity, $crate::Visibility::Private)
+    };
+
+    (module, $visibility:tt, $name:expr, $method:expr, $arity:expr) => {
+        $crate::MethodDefinition::module_function($name, $method, $arity)
+    };
+}
+
+#[macro_export]
+macro_rules! codegen_visibility {
+    (public) => { $crate::Visibility::Public };
+    (private) => { $crate::Visibility::Private };
+    (protected) => { $crate::Visibility::Protected };
+
+    // Invalid visibilities are reported by the parser, don't pile on more errors
+    ($visibility:tt) => { $crate::Visibility::Public };
+}
+
+#[macro_export]
+macro_rules! codegen_ruby_entry {
+    ($entry:ident, $call:ident, [ $({ name: $arg:tt, ty: $argty:ty, kind: required })* ]) => {
+        extern "C" fn $entry(rb_self: $crate::sys::VALUE, $($arg : $crate::sys::VALUE),*) -> $crate::sys::VALUE {
+            let result = $call(rb_self, &[$($arg),*]);
+
+            match result {
+                Ok(value) => return value,
+                Err(exception) => unsafe { exception.raise() }
+            }
+        }
+    };
+
+    ($entry:ident, $call:ident, $args:tt) => {
+        extern "C" fn $entry(argc: $crate::libc::c_int, argv: *const $crate::sys::VALUE, rb_self: $crate::sys::VALUE) -> $crate::sys::VALUE {
+            let args = if argc == 0 { &[] } else { unsafe { ::std::slice::from_raw_parts(argv, argc as usize) } };
+            let result = $call(rb_self, args);
+
+            match result {
+                Ok(value) => return value,
+                Err(exception) => unsafe { exception.raise() }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! codegen_method_arity {
+    ([ $({ name: $arg:tt, ty: $argty:ty, kind: required })* ]) => { method_arity!($($arg)*) };
+    ($args:tt) => { -1isize };
+}
+
+#[macro_export]
+macro_rules! codegen_check_arity {
+    ($args:ident, [ $({ name: $arg:tt, ty: $argty:ty, kind: required })* ]) => {
+        // Ruby already checks the arity of methods with a fixed number of arguments
+        Ok::<(), $crate::Error>(())
+    };
+
+    ($args:ident, [ $({ name: $arg:tt, ty: $argty:ty, kind: $kind:tt })* ]) => {
+        $crate::check_arity(
+            $args.len(),
+            0 $(+ codegen_count_argument!(required, $kind))*,
+            0 $(+ codegen_count_argument!(optional, $kind))*,
+            false $(|| codegen_count_argument!(rest, $kind) > 0)*
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! codegen_count_argument {
+    (required, required) => { 1 };
+    (optional, { optional: $($default:tt)* }) => { 1 };
+    (rest, { rest: $($ty:tt)* }) => { 1 };
+    ($expected:tt, $kind:tt) => { 0 };
+}
+
+#[macro_export]
+macro_rules! codegen_check_arg {
+    ($args:ident, $index:ident, $keywords:ident, $arg:tt, $argty:ty, required) => ({
+        let value = try!(<$argty as $crate::FromRubyArg>::check_arg($args[$index]));
+        $index += 1;
+        value
+    });
+
+    ($args:ident, $index:ident, $keywords:ident, $arg:tt, $argty:ty, { optional: $default:expr }) => ({
+        let value = if $index < $args.len() {
+            Some(try!(<$argty as $crate::FromRubyArg>::check_arg($args[$index])))
+        } else {
+            None
+        };
+        $index += 1;
+        value
     });
+
+    ($args:ident, $index:ident, $keywords:ident, $arg:tt, $argty:ty, { rest: $elemty:ty }) => ({
+        let mut values = Vec::with_capacity($args.len().saturating_sub($index));
+
+        while $index < $args.len() {
+            values.push(try!(<$elemty as $crate::FromRubyArg>::check_arg($args[$index])));
+            $index += 1;
+        }
+
+        values
+    });
+
+    ($args:ident, $index:ident, $keywords:ident, $arg:tt, $argty:ty, { keyword: required }) => ({
+        // `Keywords::check` already checked that required keywords are present
+        try!(<$argty as $crate::FromRubyArg>::check_arg($keywords.get(stringify!($arg)).unwrap()))
+    });
+
+    ($args:ident, $index:ident, $keywords:ident, $arg:tt, $argty:ty, { keyword: { optional: $default:expr } }) => ({
+        match $keywords.get(stringify!($arg)) {
+            Some(value) => Some(try!(<$argty as $crate::FromRubyArg>::check_arg(value))),
+            None => None
+        }
+    });
+}
+
+#[macro_export]
+macro_rules! codegen_checked_arg {
+    ($arg:ident, $argty:ty, required) => {
+        unsafe { <$argty as $crate::FromRubyArg>::from_checked_arg($arg) }
+    };
+
+    ($arg:ident, $argty:ty, { optional: $default:expr }) => {
+        match $arg {
+            Some(checked) => unsafe { <$argty as $crate::FromRubyArg>::from_checked_arg(checked) },
+            None => $default
+        }
+    };
+
+    ($arg:ident, $argty:ty, { rest: $elemty:ty }) => {
+        $arg.into_iter().map(|checked| unsafe { <$elemty as $crate::FromRubyArg>::from_checked_arg(checked) }).collect::<Vec<$elemty>>()
+    };
+
+    ($arg:ident, $argty:ty, { keyword: $kind:tt }) => {
+        codegen_checked_arg!($arg, $argty, $kind)
+    };
+}
+
+#[macro_export]
+macro_rules! codegen_keyword {
+    ($arg:tt, { keyword: required }) => { Some((stringify!($arg), true)) };
+    ($arg:tt, { keyword: $kind:tt }) => { Some((stringify!($arg), false)) };
+    ($arg:tt, $kind:tt) => { None };
+}
+
+#[macro_export]
+macro_rules! codegen_block {
+    (false) => { $crate::Block::required() };
+    (true) => { Ok::<_, $crate::Error>($crate::Block::given()) };
+}
+
+#[macro_export]
+macro_rules! codegen_check_frozen {
+    ((), $ownership:tt, $allow_frozen:tt, $rb_self:ident) => {};
+    ($struct:tt, $ownership:tt, true, $rb_self:ident) => {};
+    ($struct:tt, { & }, false, $rb_self:ident) => {};
+
+    // Methods that mutate or consume the struct can't run on a frozen object
+    ($struct:tt, $ownership:tt, false, $rb_self:ident) => {
+        try!($crate::check_frozen($rb_self));
+    };
 }
 
+#[macro_export]
+macro_rules! codegen_poison_on_panic {
+    ((), $ownership:tt, $rb_self:ident, $result:ident, $method:expr) => {};
+
+    // A panic can leave the struct half-mutated, so it shouldn't be used again.
+    // Errors raised with `raise_panic!` are deliberate, those leave the struct
+    // usable.
+    ($struct:tt, { &mut }, $rb_self:ident, $result:ident, $method:expr) => {
+        if let Err(ref payload) = $result {
+            if !payload.is::<$crate::Error>() {
+                $crate::poison($rb_self, $method);
+            }
+        }
+    };
+
+    ($struct:tt, $ownership:tt, $rb_self:ident, $result:ident, $method:expr) => {};
+}
 
 #[macro_export]
 macro_rules! codegen_self_pointer_type {
@@ -318,29 +636,31 @@ macro_rules! replace_expr {
     ($_t:tt $sub:expr) => {$sub};
 }
 
+#[macro_export]
+macro_rules! codegen_method_call {
+    (false, { $($body:tt)* }) => {
+        handle_exception! { $($body)* }
+    };
+
+    (true, { $($body:tt)* }) => {
+        $crate::without_gvl(move || catch_panic! { $($body)* })
+            .and_then(|res| res.map_err(|e| $crate::Error::from_any(e)))
+    };
+}
+
 #[macro_export]
 macro_rules! handle_exception {
     { $($body:tt)* } => {
         {
-            let hide_err = ::std::env::var("RUST_BACKTRACE").is_err();
-
-            if hide_err {
-                ::std::panic::set_hook(Box::new(|_| {
-                    // Silence
-                }));
-            }
-
-            // TODO: Poison any objects that cross the boundary to prevent them
-            // from being used in Ruby and triggering panics over and over again.
-            let res = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
-                $($body)*
-            }));
-
-            if hide_err {
-                let _ = ::std::panic::take_hook();
-            }
-
+            let res = catch_panic! { $($body)* };
             res.map_err(|e| $crate::Error::from_any(e))
         }
     }
 }
+
+#[macro_export]
+macro_rules! catch_panic {
+    { $($body:tt)* } => {
+        $crate::catch_panic(|| { $($body)* })
+    }
+}
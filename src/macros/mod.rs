@@ -19,7 +19,11 @@ macro_rules! ruby {
         parse! {
             state: top_level,
             buffer: { $($rest)* },
-            stack: { ast: [] }
+            stack: {
+                namespace: [],
+                outer: (),
+                ast: []
+            }
         }
     }
 }
@@ -26,32 +26,68 @@
         rust_name: «ident»,
         ruby_name: { string },
         meta: «Meta»,
-        struct: ‹() | { «Field»,* }›
+        struct: ‹() | { «Field»* }›
+        methods: [ «Method»,* ]
+    }
+
+  Exception :
+    {
+        type: exception,
+        rust_name: «ident»,
+        ruby_name: { string },
+        meta: «Meta»,
+        struct: (),
+        methods: []
+    }
+
+  Module :
+    {
+        type: module,
+        rust_name: «ident»,
+        ruby_name: { string },
+        meta: «Meta»,
+        struct: (),
         methods: [ «Method»,* ]
     }
 
   Meta :
     {
         pub: «bool»,
-        reopen: «bool»
+        reopen: «bool»,
+        namespace: [ { string }* ],
+        superclass: ‹() | { «path» }›,
+        marshal: «bool»,
+        shareable: «bool»
     }
 
   Field :
-    «ident» : «ty»
+    {
+        name: «ident»,
+        ty: «ty»,
+        mark: «bool»,
+        memsize: «bool»,
+        attributes: { «attribute»* }
+    }
 
   Method :
     {
         type: «MethodType»,
         rust_name: «ident»,
         ruby_name: { string },
+        ruby_visibility: «Visibility»,
+        ruby_nogvl: «bool»,
+        ruby_allow_frozen: «bool»,
         self: ‹() | «MethodSelf»›,
         args: «MethodArgs»,
+        block: [ «MethodBlock»? ],
         ret: { «ty» },
         body: «block»
     }
 
   MethodType : ‹initializer | instance_method | class_method›
 
+  Visibility : ‹public | private | protected | module_function | unexported›
+
   MethodSelf :
     {
         ownership: { «Ownership» },
@@ -59,10 +95,20 @@
     }
 
   MethodArgs :
-    [ «MethodArg»,* ]
+    [ «MethodArg»* ]
 
   MethodArg :
-    «Name» : «ty»
+    {
+        name: «Name»,
+        ty: «ty»,
+        kind: ‹required | { optional: «expr» } | { rest: «ty» } | { keyword: ‹required | { optional: «expr» }› }›
+    }
+
+  MethodBlock :
+    {
+        name: «Name»,
+        optional: «bool»
+    }
 
   Name : ‹_ | «ident»›
 */
@@ -76,11 +122,46 @@ macro_rules! parse {
     {
         state: top_level,
         buffer: {},
-        stack: { ast: $ast:tt }
+        stack: {
+            namespace: [],
+            outer: (),
+            ast: $ast:tt
+        }
     } => {
         codegen! { $ast }
     };
 
+    // Inside a `module`, the "top level" is the module body, so we resume
+    // parsing the enclosing module's methods (which will dispatch back to
+    // `parse_class` for any nested classes or modules).
+
+    {
+        state: top_level,
+        buffer: $buffer:tt,
+        stack: {
+            namespace: $namespace:tt,
+            outer: {
+                class: $class:tt,
+                program: $program:tt,
+                namespace: $outer_namespace:tt,
+                outer: $outer:tt
+            },
+            ast: $ast:tt
+        }
+    } => {
+        parse! {
+            state: parse_methods,
+            buffer: $buffer,
+            stack: {
+                class: $class,
+                program: $program,
+                namespace: $outer_namespace,
+                outer: $outer,
+                ast: $ast
+            }
+        }
+    };
+
     {
         state: top_level,
         buffer: $buffer:tt,
@@ -94,6 +175,8 @@ macro_rules! parse {
                 attributes: {},
                 pub: false,
                 reopen: false,
+                marshal: false,
+                shareable: false,
                 $($stack)*
             }
         }
@@ -119,6 +202,60 @@ macro_rules! parse {
         }
     };
 
+    {
+        state: parse_class_attributes,
+        buffer: { #[ruby_marshal] $($rest:tt)* },
+        stack: {
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            pub: $pub:tt,
+            reopen: $reopen:tt,
+            marshal: $marshal:tt,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_class_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                ruby_name: $ruby_name,
+                attributes: $attributes,
+                pub: $pub,
+                reopen: $reopen,
+                marshal: true,
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_class_attributes,
+        buffer: { #[ruby_shareable] $($rest:tt)* },
+        stack: {
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            pub: $pub:tt,
+            reopen: $reopen:tt,
+            marshal: $marshal:tt,
+            shareable: $shareable:tt,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_class_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                ruby_name: $ruby_name,
+                attributes: $attributes,
+                pub: $pub,
+                reopen: $reopen,
+                marshal: $marshal,
+                shareable: true,
+                $($stack)*
+            }
+        }
+    };
+
     {
         state: parse_class_attributes,
         buffer: { #[$($attribute:tt)*] $($rest:tt)* },
@@ -227,6 +364,9 @@ macro_rules! parse {
             attributes: $attributes:tt,
             pub: $pub:tt,
             reopen: $reopen:tt,
+            marshal: $marshal:tt,
+            shareable: $shareable:tt,
+            namespace: $namespace:tt,
 
             $($stack:tt)*
         }
@@ -240,191 +380,560 @@ macro_rules! parse {
                     rust_name: $name,
                     ruby_name: $ruby_name,
                     attributes: $attributes,
-                    meta: { pub: $pub, reopen: $reopen },
+                    meta: { pub: $pub, reopen: $reopen, namespace: $namespace, superclass: (), marshal: $marshal, shareable: $shareable },
                     struct: (),
                     methods: []
                 },
                 program: { $($rest)* },
+                namespace: $namespace,
                 $($stack)*
             }
         }
     };
 
-    // STATE: parse_struct
-
     {
-        state: parse_struct,
-        buffer: { struct { $($struct:tt)* } $($rest:tt)* },
+        state: parse_class,
+        buffer: { class $name:tt < $superclass:path { $($body:tt)* } $($rest:tt)* },
         stack: {
-            class: {
-                type: class,
-                rust_name: $rust_name:tt,
-                ruby_name: $ruby_name:tt,
-                attributes: $attributes:tt,
-                meta: { pub: $pub:tt, reopen: $reopen:tt },
-                struct: (),
-                methods: []
-            },
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            pub: $pub:tt,
+            reopen: $reopen:tt,
+            marshal: $marshal:tt,
+            shareable: $shareable:tt,
+            namespace: $namespace:tt,
+
             $($stack:tt)*
         }
     } => {
-        assert_not_reopen!({ reopen: $reopen }, "Cannot define a struct in `reopen class`");
+        assert_not_reopen!({ reopen: $reopen }, "Cannot specify a superclass in `reopen class`");
 
         parse! {
-            state: parse_methods,
-            buffer: { $($rest)* },
+            state: parse_struct,
+            buffer: { $($body)* },
             stack: {
                 class: {
                     type: class,
-                    rust_name: $rust_name,
+                    rust_name: $name,
                     ruby_name: $ruby_name,
                     attributes: $attributes,
-                    meta: { pub: $pub, reopen: $reopen },
-                    struct: { $($struct)* },
+                    meta: { pub: $pub, reopen: $reopen, namespace: $namespace, superclass: { $superclass }, marshal: $marshal, shareable: $shareable },
+                    struct: (),
                     methods: []
                 },
+                program: { $($rest)* },
+                namespace: $namespace,
                 $($stack)*
             }
         }
     };
 
     {
-        state: parse_struct,
-        buffer: $buffer:tt,
+        state: parse_class,
+        buffer: { exception $name:tt $($rest:tt)* },
+        stack: {
+            ruby_name: uninitialized,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_class,
+            buffer: { exception $name $($rest)* },
+            stack: {
+                ruby_name: { stringify!($name) },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_class,
+        buffer: { exception $name:tt ; $($rest:tt)* },
         stack: $stack:tt
     } => {
         parse! {
-            state: parse_methods,
-            buffer: $buffer,
+            state: parse_class,
+            buffer: { exception $name < ::StandardError ; $($rest)* },
             stack: $stack
         }
     };
 
-    // STATE: parse_methods
-
     {
-        state: parse_methods,
-        buffer: {},
+        state: parse_class,
+        buffer: { exception $name:tt < $superclass:path ; $($rest:tt)* },
         stack: {
-            class: $class:tt,
-            program: $program:tt,
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            pub: $pub:tt,
+            reopen: $reopen:tt,
+            marshal: $marshal:tt,
+            shareable: $shareable:tt,
+            namespace: $namespace:tt,
+            outer: $outer:tt,
             ast: [ $($ast:tt)* ]
         }
     } => {
-        assert_has_initialize!($class, "Classes defining a struct must implement `initialize`");
+        assert_not_reopen!({ reopen: $reopen }, "Cannot `reopen` an exception");
+        assert_not_marshal!({ marshal: $marshal }, "#[ruby_marshal] cannot be used on an exception");
+        assert_not_shareable!({ shareable: $shareable }, "#[ruby_shareable] cannot be used on an exception");
 
         parse! {
             state: top_level,
-            buffer: $program,
+            buffer: { $($rest)* },
             stack: {
-                ast: [ $($ast)* $class ]
+                namespace: $namespace,
+                outer: $outer,
+                ast: [ $($ast)* {
+                    type: exception,
+                    rust_name: $name,
+                    ruby_name: $ruby_name,
+                    attributes: $attributes,
+                    meta: { pub: $pub, reopen: false, namespace: $namespace, superclass: { $superclass }, marshal: false, shareable: false },
+                    struct: (),
+                    methods: []
+                } ]
             }
         }
     };
 
     {
-        state: parse_methods,
-        buffer: $buffer:tt,
-        stack: { $($stack:tt)* }
+        state: parse_class,
+        buffer: { module $name:tt $($rest:tt)* },
+        stack: {
+            ruby_name: uninitialized,
+            $($stack:tt)*
+        }
     } => {
         parse! {
-            state: parse_method_attributes,
-            buffer: $buffer,
+            state: parse_class,
+            buffer: { module $name $($rest)* },
             stack: {
-                rust_name: uninitialized,
-                ruby_name: uninitialized,
-                ruby_visibility: public,
-                attributes: {},
+                ruby_name: { stringify!($name) },
                 $($stack)*
             }
         }
     };
 
-    // STATE: parse_method_attributes
-
     {
-        state: parse_method_attributes,
-        buffer: { #[ruby_name = $ruby_name:tt] $($rest:tt)* },
+        state: parse_class,
+        buffer: { module $name:tt { $($body:tt)* } $($rest:tt)* },
         stack: {
-            rust_name: uninitialized,
-            ruby_name: uninitialized,
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            pub: $pub:tt,
+            reopen: $reopen:tt,
+            marshal: $marshal:tt,
+            shareable: $shareable:tt,
+            namespace: $namespace:tt,
+
             $($stack:tt)*
         }
     } => {
+        assert_not_reopen!({ reopen: $reopen }, "Cannot `reopen` a module, use `module` instead");
+        assert_not_marshal!({ marshal: $marshal }, "#[ruby_marshal] cannot be used on a module");
+        assert_not_shareable!({ shareable: $shareable }, "#[ruby_shareable] cannot be used on a module");
+
         parse! {
-            state: parse_method_attributes,
-            buffer: { $($rest)* },
+            state: parse_struct,
+            buffer: { $($body)* },
             stack: {
-                rust_name: uninitialized,
-                ruby_name: { $ruby_name },
+                class: {
+                    type: module,
+                    rust_name: $name,
+                    ruby_name: $ruby_name,
+                    attributes: $attributes,
+                    meta: { pub: $pub, reopen: false, namespace: $namespace, superclass: (), marshal: false, shareable: false },
+                    struct: (),
+                    methods: []
+                },
+                program: { $($rest)* },
+                namespace: $namespace,
                 $($stack)*
             }
         }
     };
 
+    // STATE: parse_struct
+
     {
-        state: parse_method_attributes,
-        buffer: { #[ruby_visibility = $ruby_visibility:tt] $($rest:tt)* },
+        state: parse_struct,
+        buffer: { struct { $($struct:tt)* } $($rest:tt)* },
         stack: {
-            rust_name: uninitialized,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: public,
+            class: {
+                type: module,
+                $($class:tt)*
+            },
             $($stack:tt)*
         }
     } => {
-        parse! {
-            state: parse_method_attributes,
-            buffer: { $($rest)* },
-            stack: {
-                rust_name: uninitialized,
-                ruby_name: $ruby_name,
-                ruby_visibility: $ruby_visibility,
-                $($stack)*
-            }
-        }
+        parse_error!("Cannot define a struct in a `module`");
     };
 
     {
-        state: parse_method_attributes,
-        buffer: { #[$($attribute:tt)*] $($rest:tt)* },
+        state: parse_struct,
+        buffer: { struct { $($struct:tt)* } $($rest:tt)* },
         stack: {
-            rust_name: uninitialized,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: $ruby_visibility:tt,
-            attributes: { $($attributes:tt)* },
+            class: {
+                type: class,
+                rust_name: $rust_name:tt,
+                ruby_name: $ruby_name:tt,
+                attributes: $attributes:tt,
+                meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+                struct: (),
+                methods: []
+            },
             $($stack:tt)*
         }
     } => {
+        assert_not_reopen!({ reopen: $reopen }, "Cannot define a struct in `reopen class`");
+
         parse! {
-            state: parse_method_attributes,
-            buffer: { $($rest)* },
+            state: parse_struct_fields,
+            buffer: { $($struct)* },
             stack: {
-                rust_name: uninitialized,
-                ruby_name: $ruby_name,
-                ruby_visibility: $ruby_visibility,
-                attributes: { $($attributes)* #[$($attribute)*] },
+                field: { mark: false, memsize: false, attributes: {} },
+                fields: [],
+                rest: { $($rest)* },
+                class: {
+                    type: class,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    attributes: $attributes,
+                    meta: { pub: $pub, reopen: $reopen, namespace: $namespace, superclass: $superclass, marshal: $marshal, shareable: $shareable },
+                    struct: (),
+                    methods: []
+                },
                 $($stack)*
             }
         }
     };
 
     {
-        state: parse_method_attributes,
+        state: parse_struct,
         buffer: $buffer:tt,
         stack: $stack:tt
     } => {
         parse! {
-            state: parse_method_name,
+            state: parse_methods,
             buffer: $buffer,
             stack: $stack
         }
     };
 
-    // STATE: parse_method_name
+    // STATE: parse_struct_fields
 
     {
-        state: parse_method_name,
-        buffer: { def $name:tt $($rest:tt)* },
+        state: parse_struct_fields,
+        buffer: {},
+        stack: {
+            field: { mark: false, memsize: false, attributes: {} },
+            fields: [ $($field:tt)* ],
+            rest: { $($rest:tt)* },
+            class: {
+                type: class,
+                rust_name: $rust_name:tt,
+                ruby_name: $ruby_name:tt,
+                attributes: $attributes:tt,
+                meta: $meta:tt,
+                struct: (),
+                methods: []
+            },
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_methods,
+            buffer: { $($rest)* },
+            stack: {
+                class: {
+                    type: class,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    attributes: $attributes,
+                    meta: $meta,
+                    struct: { $($field)* },
+                    methods: []
+                },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_struct_fields,
+        buffer: { #[ruby_mark] $($rest:tt)* },
+        stack: {
+            field: { mark: false, memsize: $memsize:tt, attributes: $attributes:tt },
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_struct_fields,
+            buffer: { $($rest)* },
+            stack: {
+                field: { mark: true, memsize: $memsize, attributes: $attributes },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_struct_fields,
+        buffer: { #[ruby_memsize] $($rest:tt)* },
+        stack: {
+            field: { mark: $mark:tt, memsize: false, attributes: $attributes:tt },
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_struct_fields,
+            buffer: { $($rest)* },
+            stack: {
+                field: { mark: $mark, memsize: true, attributes: $attributes },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_struct_fields,
+        buffer: { #[$($attribute:tt)*] $($rest:tt)* },
+        stack: {
+            field: { mark: $mark:tt, memsize: $memsize:tt, attributes: { $($attributes:tt)* } },
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_struct_fields,
+            buffer: { $($rest)* },
+            stack: {
+                field: { mark: $mark, memsize: $memsize, attributes: { $($attributes)* #[$($attribute)*] } },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_struct_fields,
+        buffer: { $vis:vis $name:ident : $ty:ty , $($rest:tt)* },
+        stack: {
+            field: { mark: $mark:tt, memsize: $memsize:tt, attributes: $attributes:tt },
+            fields: [ $($field:tt)* ],
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_struct_fields,
+            buffer: { $($rest)* },
+            stack: {
+                field: { mark: false, memsize: false, attributes: {} },
+                fields: [ $($field)* { name: $name, ty: $ty, vis: { $vis }, mark: $mark, memsize: $memsize, attributes: $attributes } ],
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_struct_fields,
+        buffer: { $vis:vis $name:ident : $ty:ty },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_struct_fields,
+            buffer: { $vis $name : $ty , },
+            stack: $stack
+        }
+    };
+
+    // STATE: parse_methods
+
+    {
+        state: parse_methods,
+        buffer: {},
+        stack: {
+            class: $class:tt,
+            program: $program:tt,
+            namespace: $namespace:tt,
+            outer: $outer:tt,
+            ast: [ $($ast:tt)* ]
+        }
+    } => {
+        assert_has_initialize!($class, "Classes defining a struct must implement `initialize`");
+        assert_valid_marshal!($class, "#[ruby_marshal] can only be used on classes with a `struct`");
+        assert_valid_shareable!($class, "#[ruby_shareable] can only be used on classes with a `struct`");
+
+        parse! {
+            state: top_level,
+            buffer: $program,
+            stack: {
+                namespace: $namespace,
+                outer: $outer,
+                ast: [ $($ast)* $class ]
+            }
+        }
+    };
+
+    {
+        state: parse_methods,
+        buffer: $buffer:tt,
+        stack: { $($stack:tt)* }
+    } => {
+        parse! {
+            state: parse_method_attributes,
+            buffer: $buffer,
+            stack: {
+                rust_name: uninitialized,
+                ruby_name: uninitialized,
+                ruby_visibility: public,
+                ruby_nogvl: false,
+                ruby_allow_frozen: false,
+                attributes: {},
+                $($stack)*
+            }
+        }
+    };
+
+    // STATE: parse_method_attributes
+
+    {
+        state: parse_method_attributes,
+        buffer: { #[ruby_name = $ruby_name:tt] $($rest:tt)* },
+        stack: {
+            rust_name: uninitialized,
+            ruby_name: uninitialized,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_method_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                rust_name: uninitialized,
+                ruby_name: { $ruby_name },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_method_attributes,
+        buffer: { #[ruby_visibility = $ruby_visibility:tt] $($rest:tt)* },
+        stack: {
+            rust_name: uninitialized,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: public,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_method_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                rust_name: uninitialized,
+                ruby_name: $ruby_name,
+                ruby_visibility: $ruby_visibility,
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_method_attributes,
+        buffer: { #[ruby_nogvl] $($rest:tt)* },
+        stack: {
+            rust_name: uninitialized,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: false,
+            $($stack:tt)*
+        }
+    } => {
+        assert_valid_nogvl!(return: $($rest)*);
+
+        parse! {
+            state: parse_method_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                rust_name: uninitialized,
+                ruby_name: $ruby_name,
+                ruby_visibility: $ruby_visibility,
+                ruby_nogvl: true,
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_method_attributes,
+        buffer: { #[ruby_allow_frozen] $($rest:tt)* },
+        stack: {
+            rust_name: uninitialized,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: false,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_method_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                rust_name: uninitialized,
+                ruby_name: $ruby_name,
+                ruby_visibility: $ruby_visibility,
+                ruby_nogvl: $ruby_nogvl,
+                ruby_allow_frozen: true,
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_method_attributes,
+        buffer: { #[$($attribute:tt)*] $($rest:tt)* },
+        stack: {
+            rust_name: uninitialized,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
+            attributes: { $($attributes:tt)* },
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_method_attributes,
+            buffer: { $($rest)* },
+            stack: {
+                rust_name: uninitialized,
+                ruby_name: $ruby_name,
+                ruby_visibility: $ruby_visibility,
+                ruby_nogvl: $ruby_nogvl,
+                ruby_allow_frozen: $ruby_allow_frozen,
+                attributes: { $($attributes)* #[$($attribute)*] },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_method_attributes,
+        buffer: $buffer:tt,
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_method_name,
+            buffer: $buffer,
+            stack: $stack
+        }
+    };
+
+    // STATE: parse_method_name
+
+    {
+        state: parse_method_name,
+        buffer: { def $name:tt $($rest:tt)* },
         stack: {
             rust_name: uninitialized,
             ruby_name: uninitialized,
@@ -462,90 +971,330 @@ macro_rules! parse {
         }
     };
 
-    // STATE: parse_method
+    // A module body can also contain nested classes and modules, which are
+    // parsed like top-level classes but inside the module's namespace. The
+    // module itself is suspended in `outer` and resumed by `top_level`.
+
+    {
+        state: parse_method_name,
+        buffer: $buffer:tt,
+        stack: {
+            rust_name: uninitialized,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: public,
+            ruby_nogvl: false,
+            ruby_allow_frozen: false,
+            attributes: $attributes:tt,
+            class: {
+                type: module,
+                rust_name: $module_rust_name:tt,
+                ruby_name: $module_ruby_name:tt,
+                attributes: $module_attributes:tt,
+                meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: [ $($namespace:tt)* ], superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+                struct: $struct:tt,
+                methods: $methods:tt
+            },
+            program: $program:tt,
+            namespace: $outer_namespace:tt,
+            outer: $outer:tt,
+            ast: $ast:tt
+        }
+    } => {
+        parse! {
+            state: parse_class,
+            buffer: $buffer,
+            stack: {
+                ruby_name: $ruby_name,
+                attributes: $attributes,
+                pub: false,
+                reopen: false,
+                marshal: false,
+                shareable: false,
+                namespace: [ $($namespace)* $module_ruby_name ],
+                outer: {
+                    class: {
+                        type: module,
+                        rust_name: $module_rust_name,
+                        ruby_name: $module_ruby_name,
+                        attributes: $module_attributes,
+                        meta: { pub: $pub, reopen: $reopen, namespace: [ $($namespace)* ], superclass: $superclass, marshal: $marshal, shareable: $shareable },
+                        struct: $struct,
+                        methods: $methods
+                    },
+                    program: $program,
+                    namespace: $outer_namespace,
+                    outer: $outer
+                },
+                ast: $ast
+            }
+        }
+    };
+
+    // STATE: parse_method
+
+    {
+        state: parse_method,
+        buffer: { ( $($args:tt)* ) $($rest:tt)* },
+        stack: {
+            rust_name: initialize,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
+            attributes: $attributes:tt,
+            class: $class:tt,
+            $($stack:tt)*
+        }
+    } => {
+        assert_not_reopen!($class, "Cannot define `initialize` in `reopen class`");
+        assert_has_struct!($class, "Cannot define `initialize` without a `struct`");
+
+        parse! {
+            state: parse_arguments_initialize,
+            buffer: { $($args)* },
+            stack: {
+                ruby_visibility: $ruby_visibility,
+                ruby_nogvl: $ruby_nogvl,
+                ruby_allow_frozen: $ruby_allow_frozen,
+                attributes: $attributes,
+                class_body: { $($rest)* },
+                class: $class,
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_method,
+        buffer: { ( $($args:tt)* ) $($rest:tt)* },
+        stack: {
+            rust_name: $rust_name:tt,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
+            attributes: $attributes:tt,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_arguments,
+            buffer: { $($args)* },
+            stack: {
+                rust_name: $rust_name,
+                ruby_name: $ruby_name,
+                ruby_visibility: $ruby_visibility,
+                ruby_nogvl: $ruby_nogvl,
+                ruby_allow_frozen: $ruby_allow_frozen,
+                attributes: $attributes,
+                class_body: { $($rest)* },
+                $($stack)*
+            }
+        }
+    };
+
+    // STATE: parse_arguments_initialize
+
+    {
+        state: parse_arguments_initialize,
+        buffer: { $helix_arg:tt, $($args:tt)+ },
+        stack: {
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
+            attributes: $attributes:tt,
+            class_body: $class_body:tt,
+            $($stack:tt)*
+        }
+    } => {
+        assert_valid_helix_arg!($helix_arg);
+
+        parse! {
+            state: parse_return_type,
+            buffer: $class_body,
+            stack: {
+                method: {
+                    type: initializer,
+                    rust_name: initialize,
+                    ruby_name: { "initialize" },
+                    ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
+                    attributes: $attributes,
+                    self: {
+                        ownership: { },
+                        name: $helix_arg
+                    },
+                    args: [ $($args)* ],
+                    block: uninitialized,
+                    ret: uninitialized,
+                    body: uninitialized
+                },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_arguments_initialize,
+        buffer: { $helix_arg:tt },
+        stack: {
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
+            attributes: $attributes:tt,
+            class_body: $class_body:tt,
+            $($stack:tt)*
+        }
+    } => {
+        assert_valid_helix_arg!($helix_arg);
+
+        parse! {
+            state: parse_return_type,
+            buffer: $class_body,
+            stack: {
+                method: {
+                    type: initializer,
+                    rust_name: initialize,
+                    ruby_name: { "initialize" },
+                    ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
+                    attributes: $attributes,
+                    self: {
+                        ownership: { },
+                        name: $helix_arg
+                    },
+                    args: [ ],
+                    block: uninitialized,
+                    ret: uninitialized,
+                    body: uninitialized
+                },
+                $($stack)*
+            }
+        }
+    };
+
+    // STATE: parse_arguments
 
     {
-        state: parse_method,
-        buffer: { ( $($args:tt)* ) $($rest:tt)* },
+        state: parse_arguments,
+        buffer: { &mut $self_arg:tt, $($args:tt)+ },
         stack: {
-            rust_name: initialize,
+            rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: $attributes:tt,
-            class: $class:tt,
+            class_body: $class_body:tt,
             $($stack:tt)*
         }
     } => {
-        assert_not_reopen!($class, "Cannot define `initialize` in `reopen class`");
-        assert_has_struct!($class, "Cannot define `initialize` without a `struct`");
+        assert_valid_self_arg!($self_arg);
 
         parse! {
-            state: parse_arguments_initialize,
-            buffer: { $($args)* },
+            state: parse_return_type,
+            buffer: $class_body,
             stack: {
-                ruby_visibility: $ruby_visibility,
-                attributes: $attributes,
-                class_body: { $($rest)* },
-                class: $class,
+                method: {
+                    type: instance_method,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
+                    attributes: $attributes,
+                    self: {
+                        ownership: { &mut },
+                        name: $self_arg
+                    },
+                    args: [ $($args)* ],
+                    block: uninitialized,
+                    ret: uninitialized,
+                    body: uninitialized
+                },
                 $($stack)*
             }
         }
     };
 
     {
-        state: parse_method,
-        buffer: { ( $($args:tt)* ) $($rest:tt)* },
+        state: parse_arguments,
+        buffer: { &mut $self_arg:tt },
         stack: {
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: $attributes:tt,
+            class_body: $class_body:tt,
             $($stack:tt)*
         }
     } => {
+        assert_valid_self_arg!($self_arg);
+
         parse! {
-            state: parse_arguments,
-            buffer: { $($args)* },
+            state: parse_return_type,
+            buffer: $class_body,
             stack: {
-                rust_name: $rust_name,
-                ruby_name: $ruby_name,
-                ruby_visibility: $ruby_visibility,
-                attributes: $attributes,
-                class_body: { $($rest)* },
+                method: {
+                    type: instance_method,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
+                    attributes: $attributes,
+                    self: {
+                        ownership: { &mut },
+                        name: $self_arg
+                    },
+                    args: [ ],
+                    block: uninitialized,
+                    ret: uninitialized,
+                    body: uninitialized
+                },
                 $($stack)*
             }
         }
     };
 
-    // STATE: parse_arguments_initialize
-
     {
-        state: parse_arguments_initialize,
-        buffer: { $helix_arg:tt, $($args:tt)+ },
+        state: parse_arguments,
+        buffer: { & $self_arg:tt, $($args:tt)+ },
         stack: {
+            rust_name: $rust_name:tt,
+            ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: $attributes:tt,
             class_body: $class_body:tt,
             $($stack:tt)*
         }
     } => {
-        assert_valid_helix_arg!($helix_arg);
+        assert_valid_self_arg!($self_arg);
 
         parse! {
             state: parse_return_type,
             buffer: $class_body,
             stack: {
                 method: {
-                    type: initializer,
-                    rust_name: initialize,
-                    ruby_name: { "initialize" },
+                    type: instance_method,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
                     self: {
-                        ownership: { },
-                        name: $helix_arg
+                        ownership: { & },
+                        name: $self_arg
                     },
                     args: [ $($args)* ],
+                    block: uninitialized,
                     ret: uninitialized,
                     body: uninitialized
                 },
@@ -555,32 +1304,39 @@ macro_rules! parse {
     };
 
     {
-        state: parse_arguments_initialize,
-        buffer: { $helix_arg:tt },
+        state: parse_arguments,
+        buffer: { & $self_arg:tt },
         stack: {
+            rust_name: $rust_name:tt,
+            ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: $attributes:tt,
             class_body: $class_body:tt,
             $($stack:tt)*
         }
     } => {
-        assert_valid_helix_arg!($helix_arg);
+        assert_valid_self_arg!($self_arg);
 
         parse! {
             state: parse_return_type,
             buffer: $class_body,
             stack: {
                 method: {
-                    type: initializer,
-                    rust_name: initialize,
-                    ruby_name: { "initialize" },
+                    type: instance_method,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
                     self: {
-                        ownership: { },
-                        name: $helix_arg
+                        ownership: { & },
+                        name: $self_arg
                     },
                     args: [ ],
+                    block: uninitialized,
                     ret: uninitialized,
                     body: uninitialized
                 },
@@ -589,15 +1345,15 @@ macro_rules! parse {
         }
     };
 
-    // STATE: parse_arguments
-
-    {
+{
         state: parse_arguments,
-        buffer: { &mut $self_arg:tt, $($args:tt)+ },
+        buffer: { $self_arg:tt, $($args:tt)+ },
         stack: {
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: $attributes:tt,
             class_body: $class_body:tt,
             $($stack:tt)*
@@ -614,12 +1370,15 @@ macro_rules! parse {
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
                     self: {
-                        ownership: { &mut },
+                        ownership: { },
                         name: $self_arg
                     },
                     args: [ $($args)* ],
+                    block: uninitialized,
                     ret: uninitialized,
                     body: uninitialized
                 },
@@ -630,11 +1389,13 @@ macro_rules! parse {
 
     {
         state: parse_arguments,
-        buffer: { &mut $self_arg:tt },
+        buffer: { $self_arg:tt },
         stack: {
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
             attributes: $attributes:tt,
             class_body: $class_body:tt,
             $($stack:tt)*
@@ -651,12 +1412,98 @@ macro_rules! parse {
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
                     self: {
-                        ownership: { &mut },
+                        ownership: { },
                         name: $self_arg
                     },
                     args: [ ],
+                    block: uninitialized,
+                    ret: uninitialized,
+                    body: uninitialized
+                },
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_arguments,
+        buffer: { $($args:tt)* },
+        stack: {
+            rust_name: $rust_name:tt,
+            ruby_name: $ruby_name:tt,
+            ruby_visibility: $ruby_visibility:tt,
+            ruby_nogvl: $ruby_nogvl:tt,
+            ruby_allow_frozen: $ruby_allow_frozen:tt,
+            attributes: $attributes:tt,
+            class_body: $class_body:tt,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_return_type,
+            buffer: $class_body,
+            stack: {
+                method: {
+                    type: class_method,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
+                    attributes: $attributes,
+                    self: (),
+                    args: [ $($args)* ],
+                    block: uninitialized,
+                    ret: uninitialized,
+                    body: uninitialized
+                },
+                $($stack)*
+            }
+        }
+    };
+
+    // STATE: parse_argument_list
+
+    {
+        state: parse_argument_list,
+        buffer: {},
+        stack: {
+            args: [ $($arg:tt)* ],
+            block: $block:tt,
+            seen: $seen:tt,
+            method: {
+                type: $type:tt,
+                rust_name: $rust_name:tt,
+                ruby_name: $ruby_name:tt,
+                ruby_visibility: $ruby_visibility:tt,
+                ruby_nogvl: $ruby_nogvl:tt,
+                ruby_allow_frozen: $ruby_allow_frozen:tt,
+                attributes: $attributes:tt,
+                self: $self:tt
+            },
+            class_body: $class_body:tt,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_return_type,
+            buffer: $class_body,
+            stack: {
+                method: {
+                    type: $type,
+                    rust_name: $rust_name,
+                    ruby_name: $ruby_name,
+                    ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
+                    attributes: $attributes,
+                    self: $self,
+                    args: [ $($arg)* ],
+                    block: $block,
                     ret: uninitialized,
                     body: uninitialized
                 },
@@ -666,187 +1513,315 @@ macro_rules! parse {
     };
 
     {
-        state: parse_arguments,
-        buffer: { & $self_arg:tt, $($args:tt)+ },
+        state: parse_argument_list,
+        buffer: { $name:tt : Block $(,)* },
+        stack: {
+            args: $args:tt,
+            block: [],
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: {},
+            stack: {
+                args: $args,
+                block: [ { name: $name, optional: false } ],
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : Option<Block> $(,)* },
+        stack: {
+            args: $args:tt,
+            block: [],
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: {},
+            stack: {
+                args: $args,
+                block: [ { name: $name, optional: true } ],
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : helix::Block $($rest:tt)* },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { $name : Block $($rest)* },
+            stack: $stack
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : Option<helix::Block> $($rest:tt)* },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { $name : Option<Block> $($rest)* },
+            stack: $stack
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : Block, $($rest:tt)+ },
+        stack: $stack:tt
+    } => {
+        parse_error!("The `Block` argument must be the last argument");
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : Option<Block>, $($rest:tt)+ },
+        stack: $stack:tt
+    } => {
+        parse_error!("The `Option<Block>` argument must be the last argument");
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { #[kw] $name:tt : $ty:ty = $default:expr, $($rest:tt)* },
+        stack: {
+            args: [ $($args:tt)* ],
+            block: $block:tt,
+            seen: $seen:tt,
+            $($stack:tt)*
+        }
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { $($rest)* },
+            stack: {
+                args: [ $($args)* { name: $name, ty: $ty, kind: { keyword: { optional: $default } } } ],
+                block: $block,
+                seen: keyword,
+                $($stack)*
+            }
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { #[kw] $name:tt : $ty:ty = $default:expr },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { #[kw] $name : $ty = $default, },
+            stack: $stack
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { #[kw] $name:tt : $ty:ty, $($rest:tt)* },
         stack: {
-            rust_name: $rust_name:tt,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: $ruby_visibility:tt,
-            attributes: $attributes:tt,
-            class_body: $class_body:tt,
+            args: [ $($args:tt)* ],
+            block: $block:tt,
+            seen: $seen:tt,
             $($stack:tt)*
         }
     } => {
-        assert_valid_self_arg!($self_arg);
-
         parse! {
-            state: parse_return_type,
-            buffer: $class_body,
+            state: parse_argument_list,
+            buffer: { $($rest)* },
             stack: {
-                method: {
-                    type: instance_method,
-                    rust_name: $rust_name,
-                    ruby_name: $ruby_name,
-                    ruby_visibility: $ruby_visibility,
-                    attributes: $attributes,
-                    self: {
-                        ownership: { & },
-                        name: $self_arg
-                    },
-                    args: [ $($args)* ],
-                    ret: uninitialized,
-                    body: uninitialized
-                },
+                args: [ $($args)* { name: $name, ty: $ty, kind: { keyword: required } } ],
+                block: $block,
+                seen: keyword,
                 $($stack)*
             }
         }
     };
 
     {
-        state: parse_arguments,
-        buffer: { & $self_arg:tt },
+        state: parse_argument_list,
+        buffer: { #[kw] $name:tt : $ty:ty },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { #[kw] $name : $ty, },
+            stack: $stack
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { * $name:tt : Vec<$elem:ty>, $($rest:tt)* },
         stack: {
-            rust_name: $rust_name:tt,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: $ruby_visibility:tt,
-            attributes: $attributes:tt,
-            class_body: $class_body:tt,
+            args: [ $($args:tt)* ],
+            block: $block:tt,
+            seen: $seen:tt,
             $($stack:tt)*
         }
     } => {
-        assert_valid_self_arg!($self_arg);
+        assert_valid_argument_order!($seen, rest);
 
         parse! {
-            state: parse_return_type,
-            buffer: $class_body,
+            state: parse_argument_list,
+            buffer: { $($rest)* },
             stack: {
-                method: {
-                    type: instance_method,
-                    rust_name: $rust_name,
-                    ruby_name: $ruby_name,
-                    ruby_visibility: $ruby_visibility,
-                    attributes: $attributes,
-                    self: {
-                        ownership: { & },
-                        name: $self_arg
-                    },
-                    args: [ ],
-                    ret: uninitialized,
-                    body: uninitialized
-                },
+                args: [ $($args)* { name: $name, ty: Vec<$elem>, kind: { rest: $elem } } ],
+                block: $block,
+                seen: rest,
                 $($stack)*
             }
         }
     };
 
-{
-        state: parse_arguments,
-        buffer: { $self_arg:tt, $($args:tt)+ },
+    {
+        state: parse_argument_list,
+        buffer: { * $name:tt : Vec<$elem:ty> },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { * $name : Vec<$elem>, },
+            stack: $stack
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { * $($rest:tt)* },
+        stack: $stack:tt
+    } => {
+        parse_error!("The splat argument must be a `Vec`, e.g. `*rest: Vec<String>`");
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : $ty:ty = $default:expr, $($rest:tt)* },
         stack: {
-            rust_name: $rust_name:tt,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: $ruby_visibility:tt,
-            attributes: $attributes:tt,
-            class_body: $class_body:tt,
+            args: [ $($args:tt)* ],
+            block: $block:tt,
+            seen: $seen:tt,
             $($stack:tt)*
         }
     } => {
-        assert_valid_self_arg!($self_arg);
+        assert_valid_argument_order!($seen, optional);
 
         parse! {
-            state: parse_return_type,
-            buffer: $class_body,
+            state: parse_argument_list,
+            buffer: { $($rest)* },
             stack: {
-                method: {
-                    type: instance_method,
-                    rust_name: $rust_name,
-                    ruby_name: $ruby_name,
-                    ruby_visibility: $ruby_visibility,
-                    attributes: $attributes,
-                    self: {
-                        ownership: { },
-                        name: $self_arg
-                    },
-                    args: [ $($args)* ],
-                    ret: uninitialized,
-                    body: uninitialized
-                },
+                args: [ $($args)* { name: $name, ty: $ty, kind: { optional: $default } } ],
+                block: $block,
+                seen: optional,
                 $($stack)*
             }
         }
     };
 
     {
-        state: parse_arguments,
-        buffer: { $self_arg:tt },
+        state: parse_argument_list,
+        buffer: { $name:tt : $ty:ty = $default:expr },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { $name : $ty = $default, },
+            stack: $stack
+        }
+    };
+
+    {
+        state: parse_argument_list,
+        buffer: { $name:tt : $ty:ty, $($rest:tt)* },
         stack: {
-            rust_name: $rust_name:tt,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: $ruby_visibility:tt,
-            attributes: $attributes:tt,
-            class_body: $class_body:tt,
+            args: [ $($args:tt)* ],
+            block: $block:tt,
+            seen: $seen:tt,
             $($stack:tt)*
         }
     } => {
-        assert_valid_self_arg!($self_arg);
+        assert_valid_argument_order!($seen, required);
 
         parse! {
-            state: parse_return_type,
-            buffer: $class_body,
+            state: parse_argument_list,
+            buffer: { $($rest)* },
             stack: {
-                method: {
-                    type: instance_method,
-                    rust_name: $rust_name,
-                    ruby_name: $ruby_name,
-                    ruby_visibility: $ruby_visibility,
-                    attributes: $attributes,
-                    self: {
-                        ownership: { },
-                        name: $self_arg
-                    },
-                    args: [ ],
-                    ret: uninitialized,
-                    body: uninitialized
-                },
+                args: [ $($args)* { name: $name, ty: $ty, kind: required } ],
+                block: $block,
+                seen: required,
                 $($stack)*
             }
         }
     };
 
     {
-        state: parse_arguments,
-        buffer: { $($args:tt)* },
+        state: parse_argument_list,
+        buffer: { $name:tt : $ty:ty },
+        stack: $stack:tt
+    } => {
+        parse! {
+            state: parse_argument_list,
+            buffer: { $name : $ty, },
+            stack: $stack
+        }
+    };
+
+    // STATE: parse_return_type
+
+    {
+        state: parse_return_type,
+        buffer: $buffer:tt,
         stack: {
-            rust_name: $rust_name:tt,
-            ruby_name: $ruby_name:tt,
-            ruby_visibility: $ruby_visibility:tt,
-            attributes: $attributes:tt,
-            class_body: $class_body:tt,
+            method: {
+                type: $type:tt,
+                rust_name: $rust_name:tt,
+                ruby_name: $ruby_name:tt,
+                ruby_visibility: $ruby_visibility:tt,
+                ruby_nogvl: $ruby_nogvl:tt,
+                ruby_allow_frozen: $ruby_allow_frozen:tt,
+                attributes: $attributes:tt,
+                self: $self:tt,
+                args: [ $($args:tt)* ],
+                block: uninitialized,
+                ret: uninitialized,
+                body: uninitialized
+            },
             $($stack:tt)*
         }
     } => {
         parse! {
-            state: parse_return_type,
-            buffer: $class_body,
+            state: parse_argument_list,
+            buffer: { $($args)* },
             stack: {
+                args: [],
+                block: [],
+                seen: required,
                 method: {
-                    type: class_method,
+                    type: $type,
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
-                    self: (),
-                    args: [ $($args)* ],
-                    ret: uninitialized,
-                    body: uninitialized
+                    self: $self
                 },
+                class_body: $buffer,
                 $($stack)*
             }
         }
     };
 
-    // STATE: parse_return_type
-
     {
         state: parse_return_type,
         buffer: { -> $ret:ty $body:block $($rest:tt)* },
@@ -856,9 +1831,12 @@ macro_rules! parse {
                 rust_name: $rust_name:tt,
                 ruby_name: $ruby_name:tt,
                 ruby_visibility: $ruby_visibility:tt,
+                ruby_nogvl: $ruby_nogvl:tt,
+                ruby_allow_frozen: $ruby_allow_frozen:tt,
                 attributes: $attributes:tt,
                 self: $self:tt,
                 args: $args:tt,
+                block: $block:tt,
                 ret: uninitialized,
                 body: uninitialized
             },
@@ -876,9 +1854,12 @@ macro_rules! parse {
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
                     self: $self,
                     args: $args,
+                    block: $block,
                     ret: { $ret },
                     body: $body
                 },
@@ -896,9 +1877,12 @@ macro_rules! parse {
                 rust_name: $rust_method_name:tt,
                 ruby_name: $ruby_method_name:tt,
                 ruby_visibility: $ruby_visibility:tt,
+                ruby_nogvl: $ruby_nogvl:tt,
+                ruby_allow_frozen: $ruby_allow_frozen:tt,
                 attributes: $metod_attributes:tt,
                 self: $self:tt,
                 args: $args:tt,
+                block: $block:tt,
                 ret: uninitialized,
                 body: uninitialized
             },
@@ -923,9 +1907,12 @@ macro_rules! parse {
                     rust_name: $rust_method_name,
                     ruby_name: $ruby_method_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $metod_attributes,
                     self: $self,
                     args: $args,
+                    block: $block,
                     ret: { $rust_class_name },
                     body: $body
                 },
@@ -952,9 +1939,12 @@ macro_rules! parse {
                 rust_name: $rust_name:tt,
                 ruby_name: $ruby_name:tt,
                 ruby_visibility: $ruby_visibility:tt,
+                ruby_nogvl: $ruby_nogvl:tt,
+                ruby_allow_frozen: $ruby_allow_frozen:tt,
                 attributes: $attributes:tt,
                 self: $self:tt,
                 args: $args:tt,
+                block: $block:tt,
                 ret: uninitialized,
                 body: uninitialized
             },
@@ -970,9 +1960,12 @@ macro_rules! parse {
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
                     ruby_visibility: $ruby_visibility,
+                    ruby_nogvl: $ruby_nogvl,
+                    ruby_allow_frozen: $ruby_allow_frozen,
                     attributes: $attributes,
                     self: $self,
                     args: $args,
+                    block: $block,
                     ret: { () },
                     body: $body
                 },
@@ -989,7 +1982,7 @@ macro_rules! parse {
         stack: {
             method: $method:tt,
             class: {
-                type: class,
+                type: $class_type:tt,
                 rust_name: $rust_name:ident,
                 ruby_name: $ruby_name:tt,
                 attributes: $attributes:tt,
@@ -1000,12 +1993,17 @@ macro_rules! parse {
             $($stack:tt)*
         }
     } => {
+        assert_valid_method_for!({ type: $class_type }, $method, "Cannot define instance methods in a `module`, only module functions (methods without `self`)");
+        assert_valid_visibility!({ type: $class_type }, $method);
+        assert_valid_nogvl!($method);
+        assert_valid_allow_frozen!($meta, $method);
+
         parse! {
             state: parse_methods,
             buffer: $buffer,
             stack: {
                 class: {
-                    type: class,
+                    type: $class_type,
                     rust_name: $rust_name,
                     ruby_name: $ruby_name,
                     attributes: $attributes,
@@ -1065,11 +2063,11 @@ macro_rules! parse_error {
 macro_rules! assert_not_reopen {
     {
         {
-            type: class,
+            type: $type:tt,
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             attributes: $attributes:tt,
-            meta: { pub: $pub:tt, reopen: $reopen:tt },
+            meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
             struct: $struct:tt,
             methods: $methods:tt
         },
@@ -1080,12 +2078,68 @@ macro_rules! assert_not_reopen {
     { { reopen: false }, $($message:expr),* } => {};
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_not_marshal {
+    { { marshal: true }, $($message:expr),* } => { parse_error!($($message),*); };
+    { { marshal: false }, $($message:expr),* } => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_not_shareable {
+    { { shareable: true }, $($message:expr),* } => { parse_error!($($message),*); };
+    { { shareable: false }, $($message:expr),* } => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_marshal {
+    {
+        {
+            type: $type:tt,
+            rust_name: $rust_name:tt,
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+            struct: $struct:tt,
+            methods: $methods:tt
+        },
+        $($message:expr),*
+    } => { assert_valid_marshal!({ marshal: $marshal, struct: $struct }, $($message),*); };
+
+    { { marshal: false, struct: $struct:tt }, $($message:expr),* } => {};
+    { { marshal: true, struct: () }, $($message:expr),* } => { parse_error!($($message),*); };
+    { { marshal: true, struct: $struct:tt }, $($message:expr),* } => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_shareable {
+    {
+        {
+            type: $type:tt,
+            rust_name: $rust_name:tt,
+            ruby_name: $ruby_name:tt,
+            attributes: $attributes:tt,
+            meta: { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+            struct: $struct:tt,
+            methods: $methods:tt
+        },
+        $($message:expr),*
+    } => { assert_valid_shareable!({ shareable: $shareable, struct: $struct }, $($message),*); };
+
+    { { shareable: false, struct: $struct:tt }, $($message:expr),* } => {};
+    { { shareable: true, struct: () }, $($message:expr),* } => { parse_error!($($message),*); };
+    { { shareable: true, struct: $struct:tt }, $($message:expr),* } => {};
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! assert_has_initialize {
     {
         {
-            type: class,
+            type: $type:tt,
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             attributes: $attributes:tt,
@@ -1109,7 +2163,7 @@ macro_rules! assert_has_initialize {
 macro_rules! assert_has_struct {
     {
         {
-            type: class,
+            type: $type:tt,
             rust_name: $rust_name:tt,
             ruby_name: $ruby_name:tt,
             attributes: $attributes:tt,
@@ -1143,3 +2197,113 @@ macro_rules! assert_no_explict_return_for_initializer {
     ({ type: class_method }, $($message:expr),*) => {};
     ({ type: initializer }, $($message:expr),*) => { parse_error!($($message),*); };
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_method_for {
+    ({ type: class }, $method:tt, $($message:expr),*) => {};
+    ({ type: module }, { type: class_method, $($rest:tt)* }, $($message:expr),*) => {};
+    ({ type: module }, { type: $type:tt, $($rest:tt)* }, $($message:expr),*) => { parse_error!($($message),*); };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_argument_order {
+    (required, $kind:tt) => {};
+    (optional, optional) => {};
+    (optional, rest) => {};
+    (optional, required) => { parse_error!("Required arguments must come before optional arguments"); };
+    (rest, rest) => { parse_error!("Only one splat argument is allowed"); };
+    (rest, $kind:tt) => { parse_error!("The splat argument must come after all other positional arguments"); };
+    (keyword, rest) => { parse_error!("The splat argument must come before keyword arguments"); };
+    (keyword, $kind:tt) => { parse_error!("Positional arguments must come before keyword arguments"); };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_nogvl {
+    ({ type: $type:tt, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, ruby_visibility: $visibility:tt, ruby_nogvl: $nogvl:tt, ruby_allow_frozen: $allow_frozen:tt, attributes: $attributes:tt, self: $self:tt, $($rest:tt)* }) => {
+        assert_valid_nogvl!($nogvl, $type, $self);
+    };
+
+    (false, $type:tt, $self:tt) => {};
+    (true, class_method, ()) => {};
+    (true, initializer, $self:tt) => {
+        parse_error!("`initialize` cannot be #[ruby_nogvl]");
+    };
+    (true, $type:tt, $self:tt) => {
+        parse_error!("#[ruby_nogvl] methods cannot take `self`, it is a Ruby object that can't be used without the GVL");
+    };
+
+    // Looks for the return type in the tokens following `#[ruby_nogvl]`. The
+    // `Error` in a `Result` would have to be created without the GVL, which
+    // allocates Ruby objects.
+    (return: -> Result $($rest:tt)*) => {
+        parse_error!("#[ruby_nogvl] methods cannot return a Result, a helix::Error can't be created without the GVL");
+    };
+    (return: -> :: $($rest:tt)*) => { assert_valid_nogvl!(return: -> $($rest)*); };
+    (return: -> $segment:ident :: $($rest:tt)*) => { assert_valid_nogvl!(return: -> $($rest)*); };
+    (return: -> $($rest:tt)*) => {};
+    (return: { $($body:tt)* } $($rest:tt)*) => {};
+    (return: $token:tt $($rest:tt)*) => { assert_valid_nogvl!(return: $($rest)*); };
+    (return: ) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_allow_frozen {
+    (
+        { pub: $pub:tt, reopen: $reopen:tt, namespace: $namespace:tt, superclass: $superclass:tt, marshal: $marshal:tt, shareable: $shareable:tt },
+        { type: $type:tt, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, ruby_visibility: $visibility:tt, ruby_nogvl: $nogvl:tt, ruby_allow_frozen: $allow_frozen:tt, attributes: $attributes:tt, self: $self:tt, $($rest:tt)* }
+    ) => {
+        assert_valid_allow_frozen!($allow_frozen, $type, $self);
+        assert_valid_allow_frozen!($allow_frozen, { shareable: $shareable });
+    };
+
+    // Frozen shareable objects can be used from several Ractors at once
+    (true, { shareable: true }) => {
+        parse_error!("#[ruby_allow_frozen] cannot be used in a #[ruby_shareable] class");
+    };
+    ($allow_frozen:tt, { shareable: $shareable:tt }) => {};
+
+    (false, $type:tt, $self:tt) => {};
+    (true, instance_method, { ownership: { &mut }, name: $name:tt }) => {};
+    (true, instance_method, { ownership: { }, name: $name:tt }) => {};
+    (true, $type:tt, $self:tt) => {
+        parse_error!("#[ruby_allow_frozen] only applies to methods that take `&mut self` or `self`");
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_valid_visibility {
+    ({ type: $class_type:tt }, { type: $type:tt, rust_name: $rust_name:tt, ruby_name: $ruby_name:tt, ruby_visibility: $visibility:tt, $($rest:tt)* }) => {
+        assert_valid_visibility!($class_type, $type, $visibility);
+    };
+
+    (class, initializer, public) => {};
+    (class, initializer, unexported) => {};
+    (class, initializer, $visibility:tt) => {
+        parse_error!("Invalid visibility `", stringify!($visibility), "` for `initialize`, expected `public` or `unexported`");
+    };
+
+    (class, $type:tt, public) => {};
+    (class, $type:tt, private) => {};
+    (class, $type:tt, protected) => {};
+    (class, $type:tt, module_function) => {
+        parse_error!("Visibility `module_function` is only allowed in a `module`");
+    };
+    (class, $type:tt, $visibility:tt) => {
+        parse_error!("Invalid visibility `", stringify!($visibility), "`, expected `public`, `private` or `protected`");
+    };
+
+    (module, class_method, public) => {};
+    (module, class_method, private) => {};
+    (module, class_method, module_function) => {};
+    (module, class_method, $visibility:tt) => {
+        parse_error!("Invalid visibility `", stringify!($visibility), "` in a `module`, expected `public`, `private` or `module_function`");
+    };
+
+    // Other methods in a module are already rejected by assert_valid_method_for!
+    (module, $type:tt, $visibility:tt) => {};
+}
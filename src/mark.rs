@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use sys::{self, VALUE};
+
+/// Marks Ruby objects reachable from a Rust value so the garbage collector
+/// keeps them alive.
+///
+/// Struct fields tagged with `#[ruby_mark]` in a `ruby!` class are marked
+/// with this trait whenever Ruby marks the wrapping object. Every Helix class
+/// implements it too, so Helix objects can be nested inside one another.
+///
+/// Objects marked with `mark` are pinned, so `GC.compact` never moves them.
+/// Types that can update their references implement `mark_movable` and
+/// `compact` as well, which lets the objects they hold be moved.
+pub trait Mark {
+    fn mark(&self);
+
+    /// Marks the Ruby objects without pinning them. Only override this
+    /// together with `compact`.
+    fn mark_movable(&self) {
+        self.mark();
+    }
+
+    /// Updates references to Ruby objects that `GC.compact` has moved.
+    fn compact(&mut self) {}
+}
+
+impl Mark for VALUE {
+    fn mark(&self) {
+        unsafe { sys::rb_gc_mark(*self) }
+    }
+
+    fn mark_movable(&self) {
+        unsafe { sys::rb_gc_mark_movable(*self) }
+    }
+
+    fn compact(&mut self) {
+        *self = unsafe { sys::rb_gc_location(*self) };
+    }
+}
+
+impl<T: Mark> Mark for Option<T> {
+    fn mark(&self) {
+        if let Some(ref value) = *self {
+            value.mark();
+        }
+    }
+
+    fn mark_movable(&self) {
+        if let Some(ref value) = *self {
+            value.mark_movable();
+        }
+    }
+
+    fn compact(&mut self) {
+        if let Some(ref mut value) = *self {
+            value.compact();
+        }
+    }
+}
+
+impl<T: Mark + ?Sized> Mark for Box<T> {
+    fn mark(&self) {
+        (**self).mark();
+    }
+
+    fn mark_movable(&self) {
+        (**self).mark_movable();
+    }
+
+    fn compact(&mut self) {
+        (**self).compact();
+    }
+}
+
+impl<T: Mark> Mark for [T] {
+    fn mark(&self) {
+        for value in self {
+            value.mark();
+        }
+    }
+
+    fn mark_movable(&self) {
+        for value in self {
+            value.mark_movable();
+        }
+    }
+
+    fn compact(&mut self) {
+        for value in self {
+            value.compact();
+        }
+    }
+}
+
+impl<T: Mark> Mark for Vec<T> {
+    fn mark(&self) {
+        self[..].mark();
+    }
+
+    fn mark_movable(&self) {
+        self[..].mark_movable();
+    }
+
+    fn compact(&mut self) {
+        self[..].compact();
+    }
+}
+
+impl<K: Eq + Hash, V: Mark, S: BuildHasher> Mark for HashMap<K, V, S> {
+    fn mark(&self) {
+        for value in self.values() {
+            value.mark();
+        }
+    }
+
+    fn mark_movable(&self) {
+        for value in self.values() {
+            value.mark_movable();
+        }
+    }
+
+    fn compact(&mut self) {
+        for value in self.values_mut() {
+            value.compact();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use bincode::{self, Options};
+use libc::c_long;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::ffi::CStr;
+use std::slice;
+use sys::{self, VALUE, c_string};
+use super::{Class, Error, ToError};
+
+/// The version of the format written by `marshal_dump`. Data written by
+/// another version is rejected by `marshal_load` instead of being misread.
+const FORMAT_VERSION: u8 = 1;
+
+/// Serializes a `#[ruby_marshal]` struct for its generated `_dump` method.
+///
+/// The result is a binary String holding the format version, the name of the
+/// wrapped Rust type and the struct encoded with bincode.
+pub fn marshal_dump<T: Serialize>(data_type: &'static sys::rb_data_type_t, value: &T) -> Result<VALUE, Error> {
+    let name = type_name(data_type);
+    let mut bytes = vec![FORMAT_VERSION];
+
+    if let Err(error) = options().serialize_into(&mut bytes, &(&name, value)) {
+        type_error!(format!("Cannot dump {}: {}", name, error));
+    }
+
+    Ok(unsafe { sys::rb_str_new(bytes.as_ptr() as c_string, bytes.len() as c_long) })
+}
+
+/// The `helix` field of a struct loaded by `marshal_load`, until `_load` sets
+/// it to the new object.
+pub fn marshal_nil() -> VALUE {
+    unsafe { sys::Qnil }
+}
+
+/// Deserializes a struct dumped by `marshal_dump`, for the generated `_load`
+/// class method. Data dumped by a different Rust type or format version
+/// raises a `TypeError`, truncated or corrupt data an `ArgumentError`.
+pub fn marshal_load<T: DeserializeOwned>(data_type: &'static sys::rb_data_type_t, data: VALUE) -> Result<T, Error> {
+    if !unsafe { sys::RB_TYPE_P(data, sys::T_STRING) } {
+        type_error!(data, "a String");
+    }
+
+    let name = type_name(data_type);
+    let bytes = unsafe { slice::from_raw_parts(sys::RSTRING_PTR(data) as *const u8, sys::RSTRING_LEN(data) as usize) };
+
+    let mut payload = match bytes.split_first() {
+        Some((&FORMAT_VERSION, payload)) => payload,
+        Some((&version, _)) => {
+            type_error!(format!("Cannot load {}, unsupported format version {} (expected {})", name, version, FORMAT_VERSION));
+        },
+        None => return Err(corrupt(&name))
+    };
+
+    // The limit makes a corrupt length prefix fail instead of allocating
+    // more memory than the data could possibly hold.
+    let limit = payload.len() as u64;
+    let dumped: String = options().with_limit(limit).deserialize_from(&mut payload).map_err(|_| corrupt(&name))?;
+
+    if dumped != name {
+        type_error!(format!("Cannot load {} from data dumped by {}", name, dumped));
+    }
+
+    let limit = payload.len() as u64;
+    let value = options().with_limit(limit).deserialize_from(&mut payload).map_err(|_| corrupt(&name))?;
+
+    if !payload.is_empty() {
+        return Err(corrupt(&name));
+    }
+
+    Ok(value)
+}
+
+// The same encoding as `bincode::serialize`
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes()
+}
+
+fn type_name(data_type: &'static sys::rb_data_type_t) -> String {
+    unsafe { CStr::from_ptr(data_type.wrap_struct_name) }.to_string_lossy().into_owned()
+}
+
+fn corrupt(name: &str) -> Error {
+    format!("Cannot load {}, the marshal data is truncated or corrupt", name)
+        .to_error()
+        .with_class(unsafe { Class::from_value(sys::rb_eArgError) })
+}
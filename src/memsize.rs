@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::mem::size_of;
+use sys::VALUE;
+
+/// Reports the heap memory owned by a Rust value, so that
+/// `ObjectSpace.memsize_of` can see it.
+///
+/// Struct fields tagged with `#[ruby_memsize]` in a `ruby!` class are added
+/// to the size of the wrapping object. Implement it for your own types to
+/// account for buffers they own.
+pub trait MemSize {
+    /// The number of bytes allocated on the heap by this value, not counting
+    /// `size_of::<Self>()` itself.
+    fn memsize(&self) -> usize;
+}
+
+macro_rules! impl_memsize_for_inline {
+    ($($ty:ty),*) => {
+        $(
+            impl MemSize for $ty {
+                fn memsize(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    }
+}
+
+impl_memsize_for_inline!(bool, char, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+// Ruby objects are accounted for by Ruby
+impl_memsize_for_inline!(VALUE);
+
+impl MemSize for String {
+    fn memsize(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: MemSize> MemSize for Option<T> {
+    fn memsize(&self) -> usize {
+        match *self {
+            Some(ref value) => value.memsize(),
+            None => 0
+        }
+    }
+}
+
+impl<T: MemSize> MemSize for Box<T> {
+    fn memsize(&self) -> usize {
+        size_of::<T>() + (**self).memsize()
+    }
+}
+
+impl<T: MemSize> MemSize for Vec<T> {
+    fn memsize(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(MemSize::memsize).sum::<usize>()
+    }
+}
+
+impl<K: Eq + Hash + MemSize, V: MemSize, S: BuildHasher> MemSize for HashMap<K, V, S> {
+    fn memsize(&self) -> usize {
+        let entries = self.capacity() * (size_of::<K>() + size_of::<V>());
+        entries + self.iter().map(|(key, value)| key.memsize() + value.memsize()).sum::<usize>()
+    }
+}
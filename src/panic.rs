@@ -0,0 +1,131 @@
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use sys::{self, ID, VALUE};
+use super::{Class, Error, FromRuby, ToError, ToRuby, protect};
+
+struct Captured {
+    location: Option<String>,
+    backtrace: Option<String>
+}
+
+thread_local! {
+    // How many Helix method calls on this thread are currently catching panics
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+
+    // Details about the last panic caught by a Helix method on this thread
+    static CAPTURED: RefCell<Option<Captured>> = const { RefCell::new(None) };
+}
+
+static INSTALL: Once = Once::new();
+static mut PANIC_CLASS: Option<Class> = None;
+static mut POISONED_CLASS: Option<Class> = None;
+
+/// Defines `Helix::Panic` and `Helix::PoisonedObjectError`, and installs
+/// the panic hook once per process.
+///
+/// The hook only handles panics raised inside a Helix method on the current
+/// thread, recording their location and backtrace. Every other panic goes to
+/// the hook that was installed before.
+pub fn install() {
+    unsafe {
+        let helix = sys::rb_define_module(cstr!("Helix"));
+        let class = sys::rb_define_class_under(helix, cstr!("Panic"), sys::rb_eRuntimeError);
+        sys::rb_define_attr(class, cstr!("location"), 1, 0);
+        sys::rb_define_attr(class, cstr!("rust_backtrace"), 1, 0);
+        PANIC_CLASS = Some(Class::from_value(class));
+
+        let class = sys::rb_define_class_under(helix, cstr!("PoisonedObjectError"), sys::rb_eRuntimeError);
+        POISONED_CLASS = Some(Class::from_value(class));
+    }
+
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(|catching| catching.get()) == 0 {
+                return previous(info);
+            }
+
+            let location = info.location().map(|location| {
+                format!("{}:{}:{}", location.file(), location.line(), location.column())
+            });
+
+            // Only captured when enabled with RUST_BACKTRACE
+            let backtrace = Backtrace::capture();
+            let backtrace = if backtrace.status() == BacktraceStatus::Captured {
+                Some(backtrace.to_string())
+            } else {
+                None
+            };
+
+            CAPTURED.with(|captured| *captured.borrow_mut() = Some(Captured { location, backtrace }));
+        }));
+    });
+}
+
+/// Runs `func`, catching any panic so it can be raised in Ruby instead.
+pub fn catch_panic<F: FnOnce() -> R, R>(func: F) -> Result<R, Box<dyn Any + Send>> {
+    CATCHING.with(|catching| catching.set(catching.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(func));
+    CATCHING.with(|catching| catching.set(catching.get() - 1));
+    result
+}
+
+/// Converts the payload of a caught panic into a `Helix::Panic` error.
+pub fn to_error(payload: Box<dyn Any>) -> Error {
+    let message = payload.downcast::<&str>().map(|message| message.to_string())
+        .or_else(|payload| payload.downcast::<String>().map(|message| *message))
+        .unwrap_or_else(|payload| format!("Unknown Error (caused by `{:?}`)", payload));
+
+    let captured = CAPTURED.with(|captured| captured.borrow_mut().take());
+    let (location, backtrace) = match captured {
+        Some(Captured { location, backtrace }) => (location, backtrace),
+        None => (None, None)
+    };
+
+    let class = unsafe { PANIC_CLASS }.expect("Helix::Panic is only defined once the extension is loaded");
+
+    unsafe {
+        let exception = sys::rb_exc_new_str(class.to_value(), message.to_ruby().unwrap());
+        sys::rb_ivar_set(exception, sys::rb_intern(cstr!("@location")), location.to_ruby().unwrap());
+        sys::rb_ivar_set(exception, sys::rb_intern(cstr!("@rust_backtrace")), backtrace.to_ruby().unwrap());
+        Error::with_exception(exception)
+    }
+}
+
+/// Marks `value` as poisoned after a panic unwound through `method`, which
+/// was mutating it.
+pub fn poison(value: VALUE, method: &str) {
+    let class = unsafe { CStr::from_ptr(sys::rb_obj_classname(value)) }.to_string_lossy();
+    let method = format!("{}#{}", class, method).to_ruby().unwrap();
+
+    // Setting an instance variable raises on frozen objects, those are left
+    // as they are.
+    let _ = protect(|| unsafe {
+        sys::rb_ivar_set(value, poisoned_ivar(), method)
+    });
+}
+
+/// Returns a `Helix::PoisonedObjectError` if `value` was poisoned by `poison`.
+pub fn check_poisoned(value: VALUE) -> Result<(), Error> {
+    let method = unsafe { sys::rb_attr_get(value, poisoned_ivar()) };
+
+    if unsafe { sys::RB_NIL_P(method) } {
+        return Ok(());
+    }
+
+    let class = unsafe { POISONED_CLASS }.expect("Helix::PoisonedObjectError is only defined once the extension is loaded");
+    // Not using `inspect` here, it could be a method of the poisoned object
+    let message = format!("This object was poisoned by a panic in {} and can no longer be used", String::from_ruby_unwrap(method));
+
+    Err(message.to_error().with_class(class))
+}
+
+// A hidden instance variable (no `@`), so it can't be seen or changed from Ruby
+fn poisoned_ivar() -> ID {
+    unsafe { sys::rb_intern(cstr!("__helix_poisoned__")) }
+}
@@ -0,0 +1,66 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use sys::{self, VALUE, EMPTY_EXCEPTION};
+use super::Error;
+
+/// Runs `func` under `rb_protect`, so that a Ruby exception (or any other
+/// non-local exit, such as `break` or `throw`) raised inside it comes back
+/// as an `Err` instead of unwinding through Rust frames.
+///
+/// The returned `Error` resumes the original exit when it is raised.
+pub fn protect<F: FnOnce() -> VALUE>(func: F) -> Result<VALUE, Error> {
+    struct Closure<F> {
+        func: Option<F>,
+        panic: CaughtPanic
+    }
+
+    extern "C" fn trampoline<F: FnOnce() -> VALUE>(data: *mut sys::void) -> VALUE {
+        let closure = unsafe { &mut *(data as *mut Closure<F>) };
+        let func = closure.func.take().expect("protect callback can only be called once");
+
+        closure.panic.catch(func).unwrap_or(unsafe { sys::Qnil })
+    }
+
+    let mut closure = Closure { func: Some(func), panic: CaughtPanic::default() };
+    let mut state = EMPTY_EXCEPTION;
+
+    let value = unsafe {
+        sys::rb_protect(trampoline::<F>, &mut closure as *mut Closure<F> as *mut sys::void, &mut state)
+    };
+
+    closure.panic.resume();
+
+    if state == EMPTY_EXCEPTION {
+        Ok(value)
+    } else {
+        Err(Error::with_tag(state))
+    }
+}
+
+/// A panic caught in a Rust callback called by Ruby.
+///
+/// A panic must never unwind through the Ruby VM, so callbacks catch it
+/// with `catch` and it is resumed with `resume` once we are back on the Rust
+/// side.
+#[derive(Default)]
+pub struct CaughtPanic(Option<Box<dyn Any + Send>>);
+
+impl CaughtPanic {
+    /// Runs `func`, returning `None` if it panicked.
+    pub fn catch<F: FnOnce() -> R, R>(&mut self, func: F) -> Option<R> {
+        match panic::catch_unwind(AssertUnwindSafe(func)) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                self.0 = Some(payload);
+                None
+            }
+        }
+    }
+
+    /// Resumes the caught panic, if any.
+    pub fn resume(self) {
+        if let Some(payload) = self.0 {
+            panic::resume_unwind(payload);
+        }
+    }
+}
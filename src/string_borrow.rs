@@ -0,0 +1,107 @@
+use std::cell::Cell;
+use std::ptr;
+use std::slice;
+use libc::c_void;
+use sys::{self, VALUE};
+use super::borrow::in_scope;
+use super::protect;
+
+struct Borrowed {
+    string: VALUE,
+    locked: bool
+}
+
+thread_local! {
+    // The strings borrowed by the Helix method calls running on this thread,
+    // owned by a hidden Ruby object that keeps them alive (see `borrowed`)
+    static BORROWED: Cell<*mut Vec<Borrowed>> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// Borrows the bytes of a Ruby String until the current Helix method call
+/// returns, without copying them.
+///
+/// The string is pinned by the GC until then, even once nothing else refers
+/// to it (e.g. a string returned by a block, or an element removed from an
+/// array argument). Unless it is frozen, it is also locked with
+/// `rb_str_locktmp`, so Ruby code that runs during the call (a block, another
+/// thread while the GVL is released) raises instead of modifying the buffer.
+///
+/// # Safety
+///
+/// Only call this inside a `BorrowScope`, and don't let the slice outlive it
+/// (see `FromRubyArg`).
+pub unsafe fn borrow_string<'a>(string: VALUE) -> &'a [u8] {
+    if in_scope() {
+        // Fails if the string is already locked, e.g. when passed as two
+        // arguments, in which case it stays locked for longer than needed
+        let locked = !sys::OBJ_FROZEN(string) && protect(|| sys::rb_str_locktmp(string)).is_ok();
+        (*borrowed()).push(Borrowed { string, locked });
+    }
+
+    slice::from_raw_parts(sys::RSTRING_PTR(string) as *const u8, sys::RSTRING_LEN(string) as usize)
+}
+
+/// The number of strings borrowed on this thread, for `BorrowScope`.
+pub fn borrowed_strings() -> usize {
+    BORROWED.with(|borrowed| unsafe { borrowed.get().as_ref() }.map_or(0, Vec::len))
+}
+
+/// Unlocks and releases the strings borrowed since `borrowed_strings`
+/// returned `start`.
+pub fn release_strings(start: usize) {
+    let borrowed = match BORROWED.with(|borrowed| unsafe { borrowed.get().as_mut() }) {
+        Some(borrowed) => borrowed,
+        None => return
+    };
+
+    for Borrowed { string, locked } in borrowed.split_off(start).into_iter().rev() {
+        if locked {
+            unsafe { sys::rb_str_unlocktmp(string) };
+        }
+    }
+}
+
+// The list of borrowed strings for this thread. It belongs to a hidden data
+// object that is never collected and marks (and pins) every string in it, so
+// that the GC sees them whichever thread it runs on. There is one per thread
+// that ever borrowed a string.
+unsafe fn borrowed() -> *mut Vec<Borrowed> {
+    BORROWED.with(|borrowed| {
+        if borrowed.get().is_null() {
+            let list = Box::into_raw(Box::new(Vec::<Borrowed>::new()));
+            // A class of 0 (Qfalse) hides the object from ObjectSpace
+            let keeper = sys::rb_data_typed_object_wrap(sys::Qfalse, list as *mut c_void, data_type());
+            sys::rb_gc_register_mark_object(keeper);
+            borrowed.set(list);
+        }
+
+        borrowed.get()
+    })
+}
+
+extern "C" fn mark(data: *mut c_void) {
+    let borrowed = unsafe { &*(data as *const Vec<Borrowed>) };
+
+    for &Borrowed { string, .. } in borrowed {
+        unsafe { sys::rb_gc_mark(string) };
+    }
+}
+
+fn data_type() -> &'static sys::rb_data_type_t {
+    static DATA_TYPE: sys::rb_data_type_t = sys::rb_data_type_t {
+        wrap_struct_name: cstr!("helix::BorrowedStrings"),
+        function: sys::rb_data_type_function_t {
+            dmark: Some(mark),
+            // Never collected
+            dfree: None,
+            dsize: None,
+            dcompact: None,
+            reserved: [ptr::null_mut(); 1]
+        },
+        parent: ptr::null(),
+        data: ptr::null_mut(),
+        flags: sys::RUBY_TYPED_FREE_IMMEDIATELY
+    };
+
+    &DATA_TYPE
+}